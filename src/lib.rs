@@ -1,257 +1,3969 @@
 #![no_std]
-use core::ops::{Index, IndexMut};
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+use core::cell::Cell;
+use core::fmt;
+use core::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub};
+use core::sync::atomic::{AtomicI16, AtomicI32, AtomicU32, Ordering};
 
 pub trait Num: Copy + Send{
     fn default_value() -> Self;
+    fn to_normalized_f32(self) -> f32;
+    fn from_normalized_f32(value: f32) -> Self;
 }
 
 impl Num for f32 {
     fn default_value() -> Self {
         0.0
     }
+
+    fn to_normalized_f32(self) -> f32 {
+        self
+    }
+
+    fn from_normalized_f32(value: f32) -> Self {
+        value
+    }
 }
 
 impl Num for i32 {
     fn default_value() -> Self {
         0
     }
+
+    fn to_normalized_f32(self) -> f32 {
+        self as f32 / 2147483648.0
+    }
+
+    fn from_normalized_f32(value: f32) -> Self {
+        (value.clamp(-1.0, 1.0) * 2147483648.0) as i32
+    }
 }
 
-pub struct ReadHead<T: Num> {
-    buffer : * const [T],
-    size : usize,
-    head_position : usize,
+impl Num for i16 {
+    fn default_value() -> Self {
+        0
+    }
+
+    fn to_normalized_f32(self) -> f32 {
+        self as f32 / 32768.0
+    }
+
+    fn from_normalized_f32(value: f32) -> Self {
+        (value.clamp(-1.0, 1.0) * 32768.0) as i16
+    }
 }
 
-unsafe impl<T: Num> Send for ReadHead<T> {}
+pub trait PcmSample: Num {
+    const BYTES: usize;
+    fn write_le_bytes(self, out: &mut [u8]);
+    fn write_be_bytes(self, out: &mut [u8]);
+    fn read_le_bytes(bytes: &[u8]) -> Self;
+}
 
-impl<T: Num> ReadHead<T> {
-    pub fn seek(&mut self, position: usize){
-        self.head_position = position % self.size;
+impl PcmSample for f32 {
+    const BYTES: usize = 4;
+
+    fn write_le_bytes(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn write_be_bytes(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+
+    fn read_le_bytes(bytes: &[u8]) -> Self {
+        f32::from_le_bytes(bytes.try_into().unwrap())
     }
 }
 
-impl<T: Num> Iterator for ReadHead<T> {
-    type Item = T;
-    fn next(&mut self) -> Option<Self::Item> {
-        let sample: T;
-        unsafe {
-            sample = (*self.buffer)[self.head_position];
-        }
-        self.head_position = (self.head_position + 1) % (self.size);
+impl PcmSample for i32 {
+    const BYTES: usize = 4;
 
-        Some(sample)
+    fn write_le_bytes(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn write_be_bytes(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+
+    fn read_le_bytes(bytes: &[u8]) -> Self {
+        i32::from_le_bytes(bytes.try_into().unwrap())
     }
 }
 
-impl<T: Num> Index<usize> for ReadHead<T> {
-    type Output = T;
-    fn index(&self, i: usize) -> &T {
-        let current_position = i % self.size;
-        unsafe {
-            &(*self.buffer)[current_position]
-        }
+impl PcmSample for i16 {
+    const BYTES: usize = 2;
+
+    fn write_le_bytes(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn write_be_bytes(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+
+    fn read_le_bytes(bytes: &[u8]) -> Self {
+        i16::from_le_bytes(bytes.try_into().unwrap())
     }
 }
 
+pub trait Bitcrush {
+    fn bitcrush(self, bits: u32) -> Self;
+}
 
-pub struct WriteHead<T: Num, const N: usize> {
-    buffer : [T; N],
-    head_position : usize,
+impl Bitcrush for f32 {
+    fn bitcrush(self, bits: u32) -> Self {
+        let step = 2.0 / ((1_u64 << bits.clamp(1, 32)) - 1) as f32;
+        ((self.clamp(-1.0, 1.0) + 1.0) / step + 0.5) as u64 as f32 * step - 1.0
+    }
 }
 
-unsafe impl<T: Num, const N: usize> Send for WriteHead<T, N> {}
+impl Bitcrush for i32 {
+    fn bitcrush(self, bits: u32) -> Self {
+        let shift = 32 - bits.clamp(1, 32);
+        (self >> shift) << shift
+    }
+}
 
-impl<T: Num, const N: usize> WriteHead<T, N> {
-    pub fn new() -> WriteHead<T, N> {
-        let buffer = [ T::default_value(); N];
-        WriteHead {buffer, head_position: 0}
+impl Bitcrush for i16 {
+    fn bitcrush(self, bits: u32) -> Self {
+        let shift = 16 - bits.clamp(1, 16);
+        (self >> shift) << shift
     }
+}
 
-    pub fn push(&mut self, element: T) {
-        self.buffer[self.head_position] = element;
-        self.increment();
+/// Samples that can be stored through an atomic of the same layout, which lets
+/// `WriteHead::split` and `WriteHead::into_heads` share a buffer between
+/// threads without data races.
+///
+/// # Safety
+///
+/// `Atomic` must have the same size, alignment and bit validity as `Self`.
+pub unsafe trait AtomicSample: Num {
+    type Atomic: Send + Sync;
+    fn new_atomic(value: Self) -> Self::Atomic;
+    fn load(cell: &Self::Atomic) -> Self;
+    fn store(cell: &Self::Atomic, value: Self);
+}
+
+unsafe impl AtomicSample for f32 {
+    type Atomic = AtomicU32;
+    fn new_atomic(value: Self) -> AtomicU32 {
+        AtomicU32::new(value.to_bits())
     }
-    
-    pub fn increment(&mut self) {
-        self.head_position = (self.head_position + 1) % self.buffer.len();
+
+    fn load(cell: &AtomicU32) -> Self {
+        f32::from_bits(cell.load(Ordering::Relaxed))
     }
 
-    pub fn seek(&mut self, position: usize){
-        self.head_position = if position > self.buffer.len() { 0 } else { position };
+    fn store(cell: &AtomicU32, value: Self) {
+        cell.store(value.to_bits(), Ordering::Relaxed);
     }
+}
 
-    pub fn clear(&mut self) where T: Default {
-        self.buffer.fill(T::default_value());
+unsafe impl AtomicSample for i32 {
+    type Atomic = AtomicI32;
+    fn new_atomic(value: Self) -> AtomicI32 {
+        AtomicI32::new(value)
     }
-    
-    pub fn as_readhead(&self, delay_samples: usize) -> ReadHead<T> {
-        ReadHead {buffer: self.buffer.as_slice(), size: self.buffer.len(), head_position: (self.buffer.len() - delay_samples) % self.buffer.len()}
+
+    fn load(cell: &AtomicI32) -> Self {
+        cell.load(Ordering::Relaxed)
+    }
+
+    fn store(cell: &AtomicI32, value: Self) {
+        cell.store(value, Ordering::Relaxed);
     }
 }
 
+unsafe impl AtomicSample for i16 {
+    type Atomic = AtomicI16;
+    fn new_atomic(value: Self) -> AtomicI16 {
+        AtomicI16::new(value)
+    }
 
-impl<T: Num, const N: usize> Iterator for WriteHead<T, N> {
-    type Item = T;
-    fn next(&mut self) -> Option<Self::Item> {
-        let sample = self.buffer[self.head_position];
-        self.increment();
+    fn load(cell: &AtomicI16) -> Self {
+        cell.load(Ordering::Relaxed)
+    }
 
-        Some(sample)
+    fn store(cell: &AtomicI16, value: Self) {
+        cell.store(value, Ordering::Relaxed);
     }
 }
 
-impl<T: Num, const N: usize> Index<usize> for WriteHead<T, N> {
-    type Output = T;
-    fn index(&self, i: usize) -> &T {
-        let current_position = i % self.buffer.len();
-        &self.buffer[current_position]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultitapError {
+    InvalidByteLength,
+}
+
+/// Q15 fixed-point sample. `Add`, `Sub`, `Mul` and `lerp` are integer-only,
+/// so `WriteHead` helpers built on them (feedback, taps) need no FPU. Helpers
+/// taking `f32` offsets or gains, such as `ReadHead::read_lerp`, still go
+/// through `to_normalized_f32`.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Q15(pub i16);
+
+impl Q15 {
+    pub fn lerp(self, other: Q15, fraction: Q15) -> Q15 {
+        let span = other.0 as i32 - self.0 as i32;
+        let value = self.0 as i32 + ((span * fraction.0 as i32) >> 15);
+        Q15(value.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
     }
 }
 
-impl<T: Num, const N: usize> IndexMut<usize> for WriteHead<T, N> {
-    fn index_mut(&mut self, i: usize) -> &mut T {
-        let current_position = i % self.buffer.len();
-        &mut self.buffer[current_position]
+impl Num for Q15 {
+    fn default_value() -> Self {
+        Q15(0)
+    }
+
+    fn to_normalized_f32(self) -> f32 {
+        self.0.to_normalized_f32()
+    }
+
+    fn from_normalized_f32(value: f32) -> Self {
+        Q15(i16::from_normalized_f32(value))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl Add for Q15 {
+    type Output = Q15;
+    fn add(self, other: Q15) -> Q15 {
+        Q15(self.0.saturating_add(other.0))
+    }
+}
 
-    #[test]
-    pub fn read_head_is_generic() {
-        {
-            let mut write_head = WriteHead::<i32, 1>::new();
-            write_head.push(0);
-        }
-        {
-            let mut write_head = WriteHead::<f32, 1>::new();
-            write_head.push(0_f32);
-        }
+impl Sub for Q15 {
+    type Output = Q15;
+    fn sub(self, other: Q15) -> Q15 {
+        Q15(self.0.saturating_sub(other.0))
     }
+}
 
-    #[test]
-    pub fn read_head_with_delay_output_equals_write_head() {
-        let mut write_head = WriteHead::<i32, 5>::new();
+impl Mul for Q15 {
+    type Output = Q15;
+    fn mul(self, other: Q15) -> Q15 {
+        let product = (self.0 as i32 * other.0 as i32) >> 15;
+        Q15(product.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+    }
+}
 
-        write_head.push(1);
-       
-        for n in 0..4 {
-            let mut read_head = write_head.as_readhead(n);
-            for j in 0..4 {
-                let val = read_head.next().unwrap();
-                if j == n {
-                    assert_eq!(val, 1)
-                }
-            }
-        }
+impl Neg for Q15 {
+    type Output = Q15;
+    fn neg(self) -> Q15 {
+        Q15(self.0.saturating_neg())
     }
+}
 
-    #[test]
-    pub fn multiple_read_head_with_delay_output_equals_write_head() {
-        let mut write_head = WriteHead::<f32, 5>::new();
+pub trait One {
+    fn one_value() -> Self;
+}
 
-        write_head.push(1.0);
-        
-        for n in 0..4 {
-            let mut read_head_1 = write_head.as_readhead(n);
-            let mut read_head_2 = write_head.as_readhead(n+1);
-            for j in 0..4 {
-                let val_1 = read_head_1.next().unwrap();
-                let val_2 = read_head_2.next().unwrap();
-                if j == n {
-                    assert_eq!(val_1, 1.0)
-                }
-                if j == (n + 1) {
-                    assert_eq!(val_2, 1.0)
-                }
+impl One for f32 {
+    fn one_value() -> Self {
+        1.0
+    }
+}
+
+impl One for i32 {
+    fn one_value() -> Self {
+        1
+    }
+}
+
+impl One for i16 {
+    fn one_value() -> Self {
+        1
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(align(16))]
+pub struct Aligned<A>(pub A);
+
+/// One cell of a `WriteHead`'s buffer, as handed out by indexing. Heads share
+/// cells while samples are pushed, so a slot is read with `get` and written
+/// with `set` or `+=` rather than through a reference to the sample.
+#[repr(transparent)]
+pub struct Slot<T: Num>(Cell<T>);
+
+impl<T: Num> Slot<T> {
+    fn from_cells(cells: &[Cell<T>]) -> &[Slot<T>] {
+        // SAFETY: `Slot<T>` is a transparent wrapper around `Cell<T>`.
+        unsafe { &*(cells as *const [Cell<T>] as *const [Slot<T>]) }
+    }
+
+    fn from_mut(sample: &mut T) -> &mut Slot<T> {
+        // SAFETY: `Slot<T>` wraps `Cell<T>`, which has the layout of `T`, and
+        // the unique borrow of `sample` carries over to the slot.
+        unsafe { &mut *(sample as *mut T as *mut Slot<T>) }
+    }
+
+    pub fn get(&self) -> T {
+        self.0.get()
+    }
+
+    pub fn set(&self, value: T) {
+        self.0.set(value);
+    }
+}
+
+impl<T: Num + AddAssign> AddAssign<T> for Slot<T> {
+    fn add_assign(&mut self, rhs: T) {
+        let sample = self.0.get_mut();
+        *sample += rhs;
+    }
+}
+
+impl<T: Num + PartialEq> PartialEq<T> for Slot<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.get() == *other
+    }
+}
+
+impl<T: Num + fmt::Debug> fmt::Debug for Slot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+/// A circular tap into a `WriteHead`. It reads the write head's cells on
+/// every access, so a tap sees samples pushed while it is alive.
+#[derive(Clone)]
+pub struct ReadHead<'a, T: Num> {
+    buffer : &'a [Slot<T>],
+    size : usize,
+    head_position : usize,
+    fade_position : usize,
+    fade_length : usize,
+    fade_remaining : usize,
+}
+
+impl<'a, T: Num> ReadHead<'a, T> {
+    fn from_cells(buffer: &'a [Slot<T>], head_position: usize) -> ReadHead<'a, T> {
+        ReadHead {buffer, size: buffer.len(), head_position, fade_position: 0, fade_length: 0, fade_remaining: 0}
+    }
+
+    pub fn seek(&mut self, position: usize){
+        self.head_position = position % self.size;
+        self.fade_remaining = 0;
+    }
+
+    pub fn seek_smooth(&mut self, position: usize, fade: usize) {
+        self.fade_position = self.head_position;
+        self.head_position = position % self.size;
+        self.fade_length = fade;
+        self.fade_remaining = fade;
+    }
+
+    pub fn scaled(self, gain: T) -> impl Iterator<Item = T> + 'a where T: Mul<Output = T> {
+        let size = self.size;
+        self.take(size).map(move |sample| sample * gain)
+    }
+
+    pub fn peek_back(&self) -> T {
+        self[self.head_position + self.size - 1].get()
+    }
+
+    pub fn argmax(&self, n: usize) -> usize where T: PartialOrd {
+        let mut max_offset = 0;
+        for i in 1..n {
+            if self[self.head_position + i].get() > self[self.head_position + max_offset].get() {
+                max_offset = i;
             }
         }
+        max_offset
     }
-    
-    #[test]
-    pub fn read_head_is_circular() {
-        let mut write_head = WriteHead::<f32, 2>::new();
-        
-        write_head.push(1.0);
 
-        let mut read_head = write_head.as_readhead(0);
-        
-        assert_eq!(read_head.next().unwrap(), 1.0);
-        assert_eq!(read_head.next().unwrap(), 0.0);
-        assert_eq!(read_head.next().unwrap(), 1.0);
+    pub fn read_lerp(&self, offset: f32) -> T {
+        let base = offset as usize;
+        let fraction = offset - base as f32;
+        if fraction == 0.0 {
+            return self[self.head_position + base].get();
+        }
+        let a = self[self.head_position + base].get().to_normalized_f32();
+        let b = self[self.head_position + base + 1].get().to_normalized_f32();
+        T::from_normalized_f32(a + (b - a) * fraction)
     }
 
-    #[test]
-    pub fn read_head_index_operator() {
-        let mut write_head = WriteHead::<f32, 5>::new();
+    pub fn read_taps(&self, taps: &[(usize, T)]) -> T where T: Add<Output = T> + Mul<Output = T> {
+        taps.iter().fold(T::default_value(), |sum, &(offset, gain)| sum + gain * self[self.head_position + offset].get())
+    }
 
-        for n in 0..4 {
-            write_head.push(n as f32);
-        }
+    pub fn read_taps_lerp(&self, taps: &[(f32, T)]) -> T where T: Add<Output = T> + Mul<Output = T> {
+        taps.iter().fold(T::default_value(), |sum, &(offset, gain)| sum + gain * self.read_lerp(offset))
+    }
 
-        let read_head = write_head.as_readhead(0);
-        for n in 0..4 {
-            assert_eq!(read_head[n], n as f32);
+    pub fn zero_crossings(&self, n: usize) -> usize where T: PartialOrd {
+        let zero = T::default_value();
+        (1..n).filter(|&i| {
+            let previous = self[self.head_position + i - 1].get();
+            let current = self[self.head_position + i].get();
+            (previous < zero) != (current < zero)
+        }).count()
+    }
+
+    pub fn read_ramp(&mut self, out: &mut [T], start_gain: f32, end_gain: f32) {
+        let steps = out.len().saturating_sub(1).max(1) as f32;
+        for (i, sample) in out.iter_mut().enumerate() {
+            let gain = start_gain + (end_gain - start_gain) * i as f32 / steps;
+            *sample = T::from_normalized_f32(self.next().unwrap().to_normalized_f32() * gain);
         }
     }
 
-    #[test]
-    pub fn read_head_index_operator_is_circular() {
-        let mut write_head = WriteHead::<f32, 2>::new();
+    pub fn eq_window(&self, expected: &[T]) -> bool where T: PartialEq {
+        expected.iter().enumerate().all(|(i, &sample)| self[self.head_position + i].get() == sample)
+    }
 
-        write_head.push(0.0);
-        write_head.push(1.0);
+    pub fn is_silent(&self, n: usize, threshold: f32) -> bool {
+        (0..n).all(|i| self[self.head_position + i].get().to_normalized_f32().abs() < threshold)
+    }
 
-        let read_head = write_head.as_readhead(0);
-        assert_eq!(read_head[0], 0.0);
-        assert_eq!(read_head[1], 1.0);
-        assert_eq!(read_head[2], 0.0);
-        assert_eq!(read_head[3], 1.0);
-        assert_eq!(read_head[4], 0.0);
+    pub fn read_modulated<U: Num>(&mut self, base: f32, lfo: &mut ReadHead<'_, U>, depth: f32) -> T {
+        let offset = (base + depth * lfo.next().unwrap().to_normalized_f32()).max(0.0);
+        let sample = self.read_lerp(offset);
+        self.head_position = (self.head_position + 1) % self.size;
+        sample
     }
-    
-    #[test]
-    pub fn write_head_is_circular() {
-        let mut write_head = WriteHead::<f32, 2>::new();
-        
-        write_head.push(0.0);
-        write_head.push(0.0);
-        write_head.push(1.0); // wraps around
 
-        let mut read_head = write_head.as_readhead(0);
-        
-        assert_eq!(read_head.next().unwrap(), 1.0);
-        assert_eq!(read_head.next().unwrap(), 0.0);
+    pub fn fir(&self, coefficients: &[T]) -> T where T: Add<Output = T> + Mul<Output = T> {
+        coefficients.iter().enumerate().fold(T::default_value(), |sum, (i, &coefficient)| sum + coefficient * self[self.head_position + i].get())
     }
-    
-    #[test]
-    pub fn write_head_index_operator() {
-        let mut write_head = WriteHead::<f32, 5>::new();
-        
-        for n in 0..4 {
-            write_head[n] = n as f32;
+
+    pub fn fir_frac(&self, taps: &[(f32, T)]) -> T where T: Add<Output = T> + Mul<Output = T> {
+        self.read_taps_lerp(taps)
+    }
+
+    pub fn geometric_taps(&self, base: usize, count: usize, decay: T) -> impl Iterator<Item = T> + '_ where T: Mul<Output = T> + One {
+        (0..count).scan(T::one_value(), move |gain, k| {
+            let delay = 1_usize.checked_shl(k as u32).and_then(|factor| base.checked_mul(factor))?;
+            let sample = *gain * self[self.head_position + delay % self.size].get();
+            *gain = *gain * decay;
+            Some(sample)
+        })
+    }
+
+    pub fn autocorr(&self, lag: usize, n: usize) -> f32 {
+        let mut shifted = self.clone();
+        shifted.seek(self.head_position + lag);
+        correlate(self, &shifted, n)
+    }
+
+    pub fn read_taps_aligned<const K: usize>(&self, offsets: [usize; K]) -> Aligned<[T; K]> {
+        Aligned(offsets.map(|offset| self[self.head_position + offset].get()))
+    }
+
+    pub fn read_enveloped(&mut self, env: &[T], out: &mut [T]) where T: Mul<Output = T> {
+        assert_eq!(env.len(), out.len());
+        for (sample, &gain) in out.iter_mut().zip(env.iter()) {
+            *sample = gain * self.next().unwrap();
         }
+    }
 
-        let mut read_head = write_head.as_readhead(0);
-        for n in 0..4 {
-            assert_eq!(read_head.next().unwrap(), n as f32);
+    pub fn read_cubic(&self, offset: f32) -> T {
+        let base = offset as usize;
+        let t = offset - base as f32;
+        let position = self.head_position + base;
+        if t == 0.0 {
+            return self[position].get();
         }
+        let y0 = self[position + self.size - 1].get().to_normalized_f32();
+        let y1 = self[position].get().to_normalized_f32();
+        let y2 = self[position + 1].get().to_normalized_f32();
+        let y3 = self[position + 2].get().to_normalized_f32();
+
+        let c1 = 0.5 * (y2 - y0);
+        let c2 = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+        let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+        T::from_normalized_f32(((c3 * t + c2) * t + c1) * t + y1)
     }
 
-    #[test]
-    pub fn write_head_index_operator_is_circular() {
-        let mut write_head = WriteHead::<f32, 2>::new();
-        
-        write_head[0] = 0.0;
-        write_head[1] = 1.0;
-        write_head[2] = 2.0;
-        write_head[3] = 3.0;
+    pub fn read_cubic_oversampled(&self, offset: f32, os: usize) -> T {
+        let os = os.max(1);
+        let mut sum = 0.0;
+        for k in 0..os {
+            let position = offset + (k as f32 - (os - 1) as f32 / 2.0) / os as f32;
+            sum += self.read_cubic(position.max(0.0)).to_normalized_f32();
+        }
+        T::from_normalized_f32(sum / os as f32)
+    }
 
-        let mut read_head = write_head.as_readhead(0);
-        assert_eq!(read_head.next().unwrap(), 2.0);
-        assert_eq!(read_head.next().unwrap(), 3.0);
+    pub fn frames(mut self) -> impl Iterator<Item = [T; 2]> + 'a {
+        let count = self.size / 2;
+        (0..count).map(move |_| [self.next().unwrap(), self.next().unwrap()])
+    }
+
+    pub fn read_spread(&self, count: usize, out: &mut [T]) {
+        assert_eq!(out.len(), count);
+        assert!(count <= self.size, "cannot spread more taps than the head has samples");
+        if count == 0 {
+            return;
+        }
+        let spacing = self.size / count;
+        for (i, sample) in out.iter_mut().enumerate() {
+            *sample = self[self.head_position + i * spacing].get();
+        }
+    }
+
+    pub fn mix_into(&mut self, out: &mut [T], gain: T) where T: AddAssign + Mul<Output = T> {
+        for sample in out.iter_mut() {
+            *sample += gain * self.next().unwrap();
+        }
+    }
+
+    pub fn take_while_read(&mut self, mut pred: impl FnMut(T) -> bool, out: &mut [T]) -> usize {
+        let mut count = 0;
+        while count < out.len() && pred(self[self.head_position].get()) {
+            out[count] = self.next().unwrap();
+            count += 1;
+        }
+        count
+    }
+
+    pub fn diff(&self, a: usize, b: usize) -> T where T: Sub<Output = T> {
+        self[self.head_position + a].get() - self[self.head_position + b].get()
+    }
+
+    pub fn read_bitcrush(&self, bits: u32) -> T where T: Bitcrush {
+        self[self.head_position].get().bitcrush(bits)
+    }
+
+    pub fn crest_factor(&self, n: usize) -> f32 {
+        if n == 0 {
+            return 0.0;
+        }
+        let mut peak = 0.0_f32;
+        let mut sum_of_squares = 0.0;
+        for i in 0..n {
+            let sample = self[self.head_position + i].get().to_normalized_f32();
+            peak = peak.max(sample.abs());
+            sum_of_squares += sample * sample;
+        }
+        let rms = sqrt(sum_of_squares / n as f32);
+        if rms == 0.0 { 0.0 } else { peak / rms }
+    }
+
+    pub fn read_dithered(&mut self, bits: u32, rng_state: &mut u32) -> T {
+        let mut uniform = || xorshift(rng_state) as f32 / u32::MAX as f32;
+        let step = 2.0 / ((1_u64 << bits.clamp(1, 32)) - 1) as f32;
+        let dither = (uniform() + uniform() - 1.0) * step;
+        let sample = self.next().unwrap().to_normalized_f32();
+        T::from_normalized_f32((sample + dither).bitcrush(bits))
+    }
+
+    pub fn next_n(&mut self, k: usize, out: &mut [T]) {
+        for sample in out[..k].iter_mut() {
+            *sample = self.next().unwrap();
+        }
+    }
+}
+
+impl<T: Num> Iterator for ReadHead<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut sample = self.buffer[self.head_position].get();
+        self.head_position = (self.head_position + 1) % (self.size);
+
+        if self.fade_remaining > 0 {
+            let previous = self.buffer[self.fade_position].get();
+            self.fade_position = (self.fade_position + 1) % (self.size);
+            let weight = (self.fade_length - self.fade_remaining + 1) as f32 / (self.fade_length + 1) as f32;
+            self.fade_remaining -= 1;
+            sample = T::from_normalized_f32(previous.to_normalized_f32() * (1.0 - weight) + sample.to_normalized_f32() * weight);
+        }
+
+        Some(sample)
+    }
+}
+
+impl<T: Num> Index<usize> for ReadHead<'_, T> {
+    type Output = Slot<T>;
+    fn index(&self, i: usize) -> &Slot<T> {
+        let current_position = i % self.size;
+        &self.buffer[current_position]
+    }
+}
+
+#[derive(Clone)]
+pub struct FractionalReadHead<'a, T: Num> {
+    read_head : ReadHead<'a, T>,
+    fraction : f32,
+}
+
+impl<'a, T: Num> FractionalReadHead<'a, T> {
+    pub fn new(read_head: ReadHead<'a, T>) -> FractionalReadHead<'a, T> {
+        FractionalReadHead {read_head, fraction: 0.0}
+    }
+
+    pub fn advance_frac(&mut self, delta: f32) {
+        let position = self.fraction + delta;
+        let mut whole = position as isize;
+        if whole as f32 > position {
+            whole -= 1;
+        }
+        self.fraction = position - whole as f32;
+        let size = self.read_head.size as isize;
+        self.read_head.head_position = (self.read_head.head_position as isize + whole % size).rem_euclid(size) as usize;
+    }
+
+    pub fn read(&self) -> T {
+        self.read_head.read_lerp(self.fraction)
+    }
+}
+
+pub struct HighpassReadHead<'a, T: Num> {
+    read_head : ReadHead<'a, T>,
+    coefficient : f32,
+    previous_input : f32,
+    previous_output : f32,
+}
+
+impl<'a, T: Num> HighpassReadHead<'a, T> {
+    pub fn new(read_head: ReadHead<'a, T>, coefficient: f32) -> HighpassReadHead<'a, T> {
+        HighpassReadHead {read_head, coefficient, previous_input: 0.0, previous_output: 0.0}
+    }
+}
+
+impl<T: Num> Iterator for HighpassReadHead<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.read_head.next()?.to_normalized_f32();
+        self.previous_output = self.coefficient * (self.previous_output + input - self.previous_input);
+        self.previous_input = input;
+
+        Some(T::from_normalized_f32(self.previous_output))
+    }
+}
+
+/// Direct form I biquad with `a0` normalized to 1; `b` are the feedforward
+/// coefficients and `a` are `[a1, a2]`.
+pub struct BiquadReadHead<'a, T: Num> {
+    read_head : ReadHead<'a, T>,
+    b : [f32; 3],
+    a : [f32; 2],
+    inputs : [f32; 2],
+    outputs : [f32; 2],
+}
+
+impl<'a, T: Num> BiquadReadHead<'a, T> {
+    pub fn new(read_head: ReadHead<'a, T>, b: [f32; 3], a: [f32; 2]) -> BiquadReadHead<'a, T> {
+        BiquadReadHead {read_head, b, a, inputs: [0.0; 2], outputs: [0.0; 2]}
+    }
+}
+
+impl<T: Num> Iterator for BiquadReadHead<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.read_head.next()?.to_normalized_f32();
+        let output = self.b[0] * input + self.b[1] * self.inputs[0] + self.b[2] * self.inputs[1]
+            - self.a[0] * self.outputs[0] - self.a[1] * self.outputs[1];
+        self.inputs = [input, self.inputs[0]];
+        self.outputs = [output, self.outputs[0]];
+
+        Some(T::from_normalized_f32(output))
+    }
+}
+
+pub struct WavetableReadHead<'a, T: Num> {
+    read_head : ReadHead<'a, T>,
+    phase : f32,
+    increment : f32,
+}
+
+impl<'a, T: Num> WavetableReadHead<'a, T> {
+    pub fn new(read_head: ReadHead<'a, T>) -> WavetableReadHead<'a, T> {
+        WavetableReadHead {read_head, phase: 0.0, increment: 0.0}
+    }
+
+    pub fn set_frequency(&mut self, freq: f32, sample_rate: f32) {
+        let increment = freq * self.read_head.size as f32 / sample_rate;
+        assert!(increment.is_finite(), "frequency and sample rate must give a finite phase increment");
+        self.increment = increment;
+    }
+}
+
+impl<T: Num> Iterator for WavetableReadHead<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.read_head.read_lerp(self.phase);
+        let size = self.read_head.size as f32;
+        self.phase = (self.phase + self.increment) % size;
+        if self.phase < 0.0 {
+            self.phase += size;
+        }
+        Some(sample)
+    }
+}
+
+pub struct AllpassReadHead<'a, T: Num> {
+    read_head : ReadHead<'a, T>,
+    offset : f32,
+    state : f32,
+}
+
+impl<'a, T: Num> AllpassReadHead<'a, T> {
+    pub fn new(read_head: ReadHead<'a, T>, offset: f32) -> AllpassReadHead<'a, T> {
+        AllpassReadHead {read_head, offset, state: 0.0}
+    }
+}
+
+impl<T: Num> Iterator for AllpassReadHead<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let base = self.offset as usize;
+        let fraction = self.offset - base as f32;
+        let coefficient = fraction / (2.0 - fraction);
+        let position = self.read_head.head_position + base;
+        let older = self.read_head[position].get().to_normalized_f32();
+        let newer = self.read_head[position + 1].get().to_normalized_f32();
+        self.state = coefficient * (newer - self.state) + older;
+        self.read_head.next()?;
+
+        Some(T::from_normalized_f32(self.state))
+    }
+}
+
+pub struct TremoloReadHead<'a, T: Num> {
+    read_head : ReadHead<'a, T>,
+    rate : f32,
+    depth : f32,
+    phase : f32,
+}
+
+impl<'a, T: Num> TremoloReadHead<'a, T> {
+    pub fn new(read_head: ReadHead<'a, T>, rate: f32, depth: f32) -> TremoloReadHead<'a, T> {
+        TremoloReadHead {read_head, rate, depth, phase: 0.0}
+    }
+}
+
+impl<T: Num> Iterator for TremoloReadHead<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let lfo = 1.0 - (2.0 * self.phase - 1.0).abs();
+        self.phase = (self.phase + self.rate) % 1.0;
+        if self.phase < 0.0 {
+            self.phase += 1.0;
+        }
+        let sample = self.read_head.next()?.to_normalized_f32();
+
+        Some(T::from_normalized_f32(sample * (1.0 - self.depth * lfo)))
+    }
+}
+
+pub struct SampleHoldReadHead<'a, T: Num> {
+    read_head : ReadHead<'a, T>,
+    hold : usize,
+    count : usize,
+}
+
+impl<'a, T: Num> SampleHoldReadHead<'a, T> {
+    pub fn new(read_head: ReadHead<'a, T>, hold: usize) -> SampleHoldReadHead<'a, T> {
+        SampleHoldReadHead {read_head, hold, count: 0}
+    }
+}
+
+impl<T: Num> Iterator for SampleHoldReadHead<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.read_head[self.read_head.head_position].get();
+        self.count += 1;
+        if self.count >= self.hold {
+            self.count = 0;
+            self.read_head.next()?;
+        }
+
+        Some(sample)
+    }
+}
+
+pub struct LinearReadHead<'a, T: Num> {
+    buffer : &'a [Slot<T>],
+    start : usize,
+    end : usize,
+    head_position : usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Underrun;
+
+impl<T: Num> LinearReadHead<'_, T> {
+    pub fn next_checked<const N: usize>(&mut self, write: &WriteHead<T, N>) -> Result<T, Underrun> {
+        assert!(core::ptr::eq(self.buffer.as_ptr(), write.cells().as_ptr()), "linear read head must come from this write head");
+        if self.head_position >= write.linear_end() {
+            return Err(Underrun);
+        }
+        let sample = self.buffer[(self.start + self.head_position) % self.buffer.len()].get();
+        self.head_position += 1;
+
+        Ok(sample)
+    }
+}
+
+impl<T: Num> Iterator for LinearReadHead<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.head_position >= self.end {
+            return None;
+        }
+        let sample = self.buffer[(self.start + self.head_position) % self.buffer.len()].get();
+        self.head_position += 1;
+
+        Some(sample)
+    }
+}
+
+
+
+pub struct WriteHead<T: Num, const N: usize> {
+    buffer : Cell<[T; N]>,
+    head_position : Cell<usize>,
+    frozen : bool,
+    active_len : usize,
+    full : Cell<bool>,
+}
+
+impl<T: Num, const N: usize> WriteHead<T, N> {
+    pub const LEN: usize = N;
+
+    pub fn new() -> WriteHead<T, N> {
+        let buffer = [ T::default_value(); N];
+        WriteHead {buffer: Cell::new(buffer), head_position: Cell::new(0), frozen: false, active_len: N, full: Cell::new(false)}
+    }
+
+    pub fn from_buffer(buffer: [T; N]) -> WriteHead<T, N> {
+        WriteHead {buffer: Cell::new(buffer), head_position: Cell::new(0), frozen: false, active_len: N, full: Cell::new(true)}
+    }
+
+    pub fn from_reversed(mut data: [T; N]) -> WriteHead<T, N> {
+        data.reverse();
+        WriteHead::from_buffer(data)
+    }
+
+    pub fn from_tail(data: &[T]) -> WriteHead<T, N> {
+        let mut write_head = WriteHead::new();
+        write_head.prime(&data[data.len().saturating_sub(N)..]);
+        write_head
+    }
+
+    pub fn from_bytes_le(bytes: &[u8]) -> Result<WriteHead<T, N>, MultitapError> where T: PcmSample {
+        if bytes.len() != N * T::BYTES {
+            return Err(MultitapError::InvalidByteLength);
+        }
+        let mut write_head = WriteHead::new();
+        for (sample, chunk) in write_head.buffer.get_mut().iter_mut().zip(bytes.chunks_exact(T::BYTES)) {
+            *sample = T::read_le_bytes(chunk);
+        }
+        write_head.full.set(true);
+        Ok(write_head)
+    }
+
+    pub fn impulse() -> WriteHead<T, N> where T: One {
+        let mut buffer = [T::default_value(); N];
+        buffer[0] = T::one_value();
+        WriteHead::from_buffer(buffer)
+    }
+
+    pub fn ones() -> WriteHead<T, N> where T: One {
+        WriteHead::from_buffer([T::one_value(); N])
+    }
+
+    pub fn push(&self, element: T) {
+        if self.frozen {
+            return;
+        }
+        self.cells()[self.head_position.get()].set(element);
+        self.increment();
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+    
+    pub fn increment(&self) {
+        let position = (self.head_position.get() + 1) % self.active_len;
+        self.head_position.set(position);
+        if position == 0 {
+            self.full.set(true);
+        }
+    }
+
+    pub fn seek(&mut self, position: usize){
+        self.head_position.set(if position > self.active_len { 0 } else { position % self.active_len });
+    }
+
+    pub fn set_active_len(&mut self, active_len: usize) {
+        self.active_len = active_len.clamp(1, N);
+        *self.head_position.get_mut() %= self.active_len;
+    }
+
+    pub fn active_len(&self) -> usize {
+        self.active_len
+    }
+
+    /// Zeroes the storage and rewinds the write position, so the buffer reads
+    /// as empty again.
+    pub fn clear(&mut self) where T: Default {
+        self.buffer.get_mut().fill(T::default_value());
+        self.head_position.set(0);
+        self.full.set(false);
+    }
+    
+    pub fn as_readhead(&self, delay_samples: usize) -> ReadHead<'_, T> {
+        ReadHead::from_cells(&self.cells()[..self.active_len], (self.active_len - delay_samples % self.active_len) % self.active_len)
+    }
+
+    fn cells(&self) -> &[Slot<T>] {
+        let buffer: &Cell<[T]> = &self.buffer;
+        Slot::from_cells(buffer.as_slice_of_cells())
+    }
+
+    /// Borrows the whole buffer mutably through a shared reference.
+    ///
+    /// # Safety
+    ///
+    /// Nothing else may read or write the buffer while the slice is alive:
+    /// no `push`, no indexing, and no read head created from this write head.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn data_mut(&self) -> &mut [T] {
+        &mut *self.buffer.as_ptr()
+    }
+
+    pub fn resize_into<const M: usize>(&self) -> WriteHead<T, M> {
+        let mut resized = WriteHead::<T, M>::new();
+        resized.active_len = if self.active_len == N { M } else { self.active_len.min(M) };
+        resized.frozen = self.frozen;
+        let count = self.linear_end().min(resized.active_len);
+        for (n, sample) in resized.buffer.get_mut()[..count].iter_mut().enumerate() {
+            *sample = self.ago(count - n);
+        }
+        resized.head_position.set(count % resized.active_len);
+        resized.full.set(count == resized.active_len);
+        resized
+    }
+
+    fn convert<U: Num>(&self) -> WriteHead<U, N> {
+        let buffer = self.buffer.get().map(|sample| U::from_normalized_f32(sample.to_normalized_f32()));
+        WriteHead {buffer: Cell::new(buffer), head_position: self.head_position.clone(), frozen: self.frozen, active_len: self.active_len, full: self.full.clone()}
+    }
+
+    /// A non-wrapping head over `[0, write position)`, or, once the write head
+    /// has wrapped, the whole active region from the oldest sample onwards.
+    pub fn as_linear_readhead(&self) -> LinearReadHead<'_, T> {
+        let start = if self.full.get() { self.head_position.get() } else { 0 };
+        LinearReadHead {buffer: &self.cells()[..self.active_len], start, end: self.linear_end(), head_position: 0}
+    }
+
+    fn linear_end(&self) -> usize {
+        if self.full.get() { self.active_len } else { self.head_position.get() }
+    }
+
+    pub fn prime(&mut self, data: &[T]) {
+        let count = data.len().min(self.active_len);
+        self.buffer.get_mut()[..count].copy_from_slice(&data[..count]);
+        self.head_position.set(count % self.active_len);
+        self.full.set(count == self.active_len);
+    }
+
+    pub fn ago(&self, n: usize) -> T {
+        self[self.head_position.get() + self.active_len - n % self.active_len].get()
+    }
+
+    pub fn fill_noise(&mut self, seed: u32) {
+        let mut state = seed;
+        let active_len = self.active_len;
+        for sample in self.buffer.get_mut()[..active_len].iter_mut() {
+            *sample = T::from_normalized_f32(xorshift(&mut state) as f32 / u32::MAX as f32 * 2.0 - 1.0);
+        }
+    }
+
+    pub fn push_feedback_saturating(&self, element: T, feedback: T, delay: usize, limit: T) -> bool where T: Add<Output = T> + Mul<Output = T> + Neg<Output = T> + PartialOrd {
+        let sample = element + feedback * self.ago(delay);
+        let (sample, saturated) = if sample > limit {
+            (limit, true)
+        } else if sample < -limit {
+            (-limit, true)
+        } else {
+            (sample, false)
+        };
+        self.push(sample);
+        saturated
+    }
+
+    /// Sample under the write head: the oldest cell, overwritten by the next `push`.
+    /// The most recently written sample is `ago(1)`.
+    pub fn current(&self) -> T {
+        self.cells()[self.head_position.get()].get()
+    }
+
+    pub fn into_samples(self) -> core::array::IntoIter<T, N> {
+        self.buffer.into_inner().into_iter()
+    }
+
+    pub fn deinterleave(&self, left: &mut [T], right: &mut [T]) {
+        assert!(left.len() == N / 2 && right.len() == N / 2);
+        for (n, frame) in self.cells().chunks_exact(2).enumerate() {
+            left[n] = frame[0].get();
+            right[n] = frame[1].get();
+        }
+    }
+
+    pub fn interleave(&mut self, left: &[T], right: &[T]) {
+        assert!(left.len() == N / 2 && right.len() == N / 2);
+        for (n, frame) in self.buffer.get_mut().chunks_exact_mut(2).enumerate() {
+            frame[0] = left[n];
+            frame[1] = right[n];
+        }
+    }
+
+    /// Like `as_readhead`, but the delay is checked against `N` at compile time.
+    ///
+    /// ```compile_fail
+    /// let write_head = multitap::WriteHead::<f32, 4>::new();
+    /// let read_head = write_head.as_readhead_const::<4>();
+    /// ```
+    pub fn as_readhead_const<const D: usize>(&self) -> ReadHead<'_, T> {
+        const { assert!(D < N, "tap delay must be less than the buffer length") };
+        self.as_readhead(D)
+    }
+
+    pub fn read_storage(&self, out: &mut [T]) {
+        for (sample, stored) in out.iter_mut().zip(self.cells()) {
+            *sample = stored.get();
+        }
+    }
+
+    pub fn add_slice(&mut self, data: &[T]) where T: AddAssign {
+        let position = self.head_position.get();
+        for (i, &sample) in data.iter().enumerate() {
+            self[position + i] += sample;
+        }
+    }
+
+    pub fn reverse(&mut self) {
+        let active_len = self.active_len;
+        self.buffer.get_mut()[..active_len].reverse();
+    }
+
+    pub fn push_gated(&self, element: T, gate: bool) {
+        if gate {
+            self.push(element);
+        }
+    }
+
+    pub fn as_loop_readhead(&self, start: usize, len: usize) -> ReadHead<'_, T> {
+        assert!(len > 0, "loop length must be non-zero");
+        let end = match start.checked_add(len) {
+            Some(end) if end <= self.active_len => end,
+            _ => panic!("loop region must lie within the active region"),
+        };
+        ReadHead::from_cells(&self.cells()[start..end], 0)
+    }
+
+    /// `f` gets this write head and one read head over its buffer. The read
+    /// head starts on the write position and persists across the block.
+    pub fn process_block(&self, input: &[T], output: &mut [T], mut f: impl FnMut(&WriteHead<T, N>, &mut ReadHead<'_, T>, T) -> T) {
+        let mut read_head = self.as_colocated_readhead();
+        for (&sample, out) in input.iter().zip(output.iter_mut()) {
+            *out = f(self, &mut read_head, sample);
+        }
+    }
+
+    pub fn as_readhead_ratio(&self, r: f32) -> ReadHead<'_, T> {
+        let delay_samples = (r.clamp(0.0, 1.0) * self.active_len as f32 + 0.5) as usize;
+        self.as_readhead(delay_samples.min(self.max_delay()))
+    }
+
+    pub fn sum_samples(&self) -> T where T: Add<Output = T> {
+        self.cells()[..self.active_len].iter().fold(T::default_value(), |sum, sample| sum + sample.get())
+    }
+
+    pub fn normalize(&mut self) {
+        let active_len = self.active_len;
+        let peak = self.cells()[..active_len].iter().fold(0.0_f32, |peak, sample| peak.max(sample.get().to_normalized_f32().abs()));
+        if peak > 1.0 {
+            for sample in self.buffer.get_mut()[..active_len].iter_mut() {
+                *sample = T::from_normalized_f32(sample.to_normalized_f32() / peak);
+            }
+        }
+    }
+
+    pub fn fill_ramp(&mut self, start: T, step: T) where T: Add<Output = T> {
+        let mut value = start;
+        let active_len = self.active_len;
+        for sample in self.buffer.get_mut()[..active_len].iter_mut() {
+            *sample = value;
+            value = value + step;
+        }
+    }
+
+    pub fn behind(&self, i: usize) -> T {
+        self.ago(i)
+    }
+
+    #[cfg(feature = "heapless")]
+    pub fn to_heapless(&self) -> heapless::Vec<T, N> {
+        self.cells()[..self.active_len].iter().map(Slot::get).collect()
+    }
+
+    pub fn ago_lerp(&self, n: f32) -> T {
+        let base = n as usize;
+        let fraction = n - base as f32;
+        if fraction == 0.0 {
+            return self.ago(base);
+        }
+        let a = self.ago(base).to_normalized_f32();
+        let b = self.ago(base + 1).to_normalized_f32();
+        T::from_normalized_f32(a + (b - a) * fraction)
+    }
+
+    /// Splits the buffer into its single `Writer` and a `Reader` tapping it at
+    /// `delay_samples`. Both halves are `Send` and access every cell atomically,
+    /// so a reader running alongside the writer sees each sample either before
+    /// or after a push, never torn; ordering across cells is not guaranteed.
+    pub fn split(&mut self, delay_samples: usize) -> (Writer<'_, T>, Reader<'_, T>) where T: AtomicSample {
+        const { assert!(core::mem::size_of::<T>() == core::mem::size_of::<T::Atomic>() && core::mem::align_of::<T>() == core::mem::align_of::<T::Atomic>()) };
+        let active_len = self.active_len;
+        let storage: *mut [T] = &mut self.buffer.get_mut()[..active_len];
+        // SAFETY: `AtomicSample` guarantees `T::Atomic` has the layout and bit
+        // validity of `T`, and the `&mut self` borrow both halves hold keeps any
+        // other access to the buffer out until they are dropped.
+        let cells = unsafe { &*(storage as *const [T::Atomic]) };
+        let reader = Reader {cells, head_position: (active_len - delay_samples % active_len) % active_len};
+        let writer = Writer {cells, head_position: self.head_position.get_mut(), full: self.full.get_mut(), active_len, frozen: self.frozen};
+        (writer, reader)
+    }
+
+    /// Like `split`, but consumes the write head so the halves own the active
+    /// region and can be moved into threads that outlive it. The reader starts
+    /// on the write position, i.e. on the oldest sample.
+    #[cfg(feature = "alloc")]
+    pub fn into_heads(self) -> (OwnedWriter<T>, OwnedReader<T>) where T: AtomicSample {
+        let head_position = self.head_position.get();
+        let cells: Arc<[T::Atomic]> = self.buffer.into_inner()[..self.active_len].iter().map(|&sample| T::new_atomic(sample)).collect();
+        let reader = OwnedReader {cells: cells.clone(), head_position};
+        let writer = OwnedWriter {cells, head_position, frozen: self.frozen};
+        (writer, reader)
+    }
+
+    /// Mean of the active region in normalized units, so integer samples
+    /// are scaled to [-1, 1] first: `[i16::MIN, 0]` averages to -0.5.
+    pub fn mean(&self) -> f32 {
+        self.cells()[..self.active_len].iter().map(|sample| sample.get().to_normalized_f32()).sum::<f32>() / self.active_len as f32
+    }
+
+    pub fn clamp(&mut self, lo: T, hi: T) where T: PartialOrd {
+        let active_len = self.active_len;
+        for sample in self.buffer.get_mut()[..active_len].iter_mut() {
+            if *sample < lo {
+                *sample = lo;
+            } else if *sample > hi {
+                *sample = hi;
+            }
+        }
+    }
+
+    /// Places the head `delay` cells behind the write head's current position
+    /// (unlike `as_readhead`, which counts from cell 0). A negative `delay`
+    /// places it ahead, on stale samples the write head has yet to overwrite.
+    /// Delays of any magnitude wrap modulo the active length.
+    pub fn as_readhead_signed(&self, delay: isize) -> ReadHead<'_, T> {
+        let position = (self.head_position.get() as isize - delay).rem_euclid(self.active_len as isize) as usize;
+        ReadHead::from_cells(&self.cells()[..self.active_len], position)
+    }
+
+    pub fn nonzero_count(&self) -> usize where T: PartialEq {
+        self.cells()[..self.active_len].iter().filter(|sample| sample.get() != T::default_value()).count()
+    }
+
+    pub fn to_bytes_le(&self, out: &mut [u8]) -> Result<(), MultitapError> where T: PcmSample {
+        if out.len() < N * T::BYTES {
+            return Err(MultitapError::InvalidByteLength);
+        }
+        for (sample, bytes) in self.cells().iter().zip(out.chunks_exact_mut(T::BYTES)) {
+            sample.get().write_le_bytes(bytes);
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes_be(&self, out: &mut [u8]) -> Result<(), MultitapError> where T: PcmSample {
+        if out.len() < N * T::BYTES {
+            return Err(MultitapError::InvalidByteLength);
+        }
+        for (sample, bytes) in self.cells().iter().zip(out.chunks_exact_mut(T::BYTES)) {
+            sample.get().write_be_bytes(bytes);
+        }
+        Ok(())
+    }
+
+    pub fn max_delay(&self) -> usize {
+        self.active_len - 1
+    }
+
+    pub fn push_blend(&self, element: T, mix: f32) {
+        let sample = if mix == 1.0 {
+            element
+        } else if mix == 0.0 {
+            self.current()
+        } else {
+            let existing = self.current().to_normalized_f32();
+            T::from_normalized_f32(mix * element.to_normalized_f32() + (1.0 - mix) * existing)
+        };
+        self.push(sample);
+    }
+
+    pub fn push_with_wrap(&self, element: T) -> bool {
+        if self.frozen {
+            return false;
+        }
+        self.push(element);
+        self.head_position.get() == 0
+    }
+
+    pub fn rewind_to_start(&mut self) {
+        self.head_position.set(0);
+    }
+
+    pub fn copy_window_from(&mut self, src: &WriteHead<T, N>, dst_start: usize, src_start: usize, len: usize) {
+        for n in 0..len {
+            self[dst_start + n].set(src[src_start + n].get());
+        }
+    }
+
+    /// Pushes `element` after reflecting any excursion beyond `±threshold`
+    /// back into range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is not positive.
+    pub fn push_foldback(&self, element: T, threshold: T) {
+        let threshold = threshold.to_normalized_f32();
+        assert!(threshold > 0.0, "foldback threshold must be positive");
+        let period = 4.0 * threshold;
+        let mut phase = (element.to_normalized_f32() + threshold) % period;
+        if phase < 0.0 {
+            phase += period;
+        }
+        let folded = if phase <= 2.0 * threshold { phase - threshold } else { 3.0 * threshold - phase };
+        self.push(T::from_normalized_f32(folded));
+    }
+
+    pub fn oldest(&self) -> T {
+        self.ago(self.active_len)
+    }
+
+    pub fn newest(&self) -> T {
+        self.ago(1)
+    }
+
+    /// Minimum, maximum and mean of the active region. `min` and `max` are
+    /// raw samples, while the mean is normalized like `mean`: i16 `[100, 300]`
+    /// gives `(100, 300, 0.0061)`.
+    pub fn stats(&self) -> (T, T, f32) where T: PartialOrd {
+        let (mut min, mut max) = (self[0].get(), self[0].get());
+        for sample in self.cells()[..self.active_len].iter().map(Slot::get) {
+            if sample < min {
+                min = sample;
+            }
+            if sample > max {
+                max = sample;
+            }
+        }
+        (min, max, self.mean())
+    }
+
+    /// Scales every cell by `factor`, regardless of `is_frozen`, so a frozen
+    /// loop can be faded out.
+    pub fn decay(&mut self, factor: T) where T: Mul<Output = T> {
+        let active_len = self.active_len;
+        for sample in self.buffer.get_mut()[..active_len].iter_mut() {
+            *sample = *sample * factor;
+        }
+    }
+
+    pub fn as_colocated_readhead(&self) -> ReadHead<'_, T> {
+        ReadHead::from_cells(&self.cells()[..self.active_len], self.head_position.get() % self.active_len)
+    }
+
+    pub fn clip_count(&self, threshold: T) -> usize where T: Neg<Output = T> + PartialOrd {
+        self.cells()[..self.active_len].iter().map(Slot::get).filter(|&sample| sample >= threshold || sample <= -threshold).count()
+    }
+
+    pub fn apply_window(&mut self, window: &[T]) where T: Mul<Output = T> {
+        let active_len = self.active_len;
+        assert_eq!(window.len(), active_len, "window must cover the active region");
+        for (sample, &coefficient) in self.buffer.get_mut()[..active_len].iter_mut().zip(window) {
+            *sample = *sample * coefficient;
+        }
+    }
+}
+
+impl<T: Num, const N: usize> Default for WriteHead<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> WriteHead<i16, N> {
+    pub fn to_f32(&self) -> WriteHead<f32, N> {
+        self.convert()
+    }
+}
+
+impl<const N: usize> WriteHead<f32, N> {
+    pub fn to_i16(&self) -> WriteHead<i16, N> {
+        self.convert()
+    }
+}
+
+
+impl<T: Num, const N: usize> Iterator for WriteHead<T, N> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.current();
+        self.increment();
+
+        Some(sample)
+    }
+}
+
+impl<T: Num, const N: usize> Index<usize> for WriteHead<T, N> {
+    type Output = Slot<T>;
+    fn index(&self, i: usize) -> &Slot<T> {
+        let current_position = i % self.active_len;
+        &self.cells()[current_position]
+    }
+}
+
+impl<T: Num, const N: usize> IndexMut<usize> for WriteHead<T, N> {
+    fn index_mut(&mut self, i: usize) -> &mut Slot<T> {
+        let current_position = i % self.active_len;
+        Slot::from_mut(&mut self.buffer.get_mut()[current_position])
+    }
+}
+
+/// Advances a xorshift32 state. Zero is a fixed point, so a zero seed is
+/// replaced first.
+fn xorshift(state: &mut u32) -> u32 {
+    if *state == 0 {
+        *state = 0x9e3779b9;
+    }
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+    *state
+}
+
+fn sqrt(x: f32) -> f32 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut estimate = f32::from_bits((x.to_bits() >> 1) + 0x1fbd_1df5);
+    for _ in 0..3 {
+        estimate = 0.5 * (estimate + x / estimate);
+    }
+    estimate
+}
+
+pub fn correlate<T: Num>(a: &ReadHead<'_, T>, b: &ReadHead<'_, T>, n: usize) -> f32 {
+    let mut sum = 0.0;
+    for i in 0..n {
+        sum += a[a.head_position + i].get().to_normalized_f32() * b[b.head_position + i].get().to_normalized_f32();
+    }
+    sum
+}
+
+/// Phase correlation of the next `n` samples, in `[-1, 1]`; silent input reports 0.
+pub fn stereo_correlation<T: Num>(l: &ReadHead<'_, T>, r: &ReadHead<'_, T>, n: usize) -> f32 {
+    let (mut cross, mut left_energy, mut right_energy) = (0.0, 0.0, 0.0);
+    for i in 0..n {
+        let left = l[l.head_position + i].get().to_normalized_f32();
+        let right = r[r.head_position + i].get().to_normalized_f32();
+        cross += left * right;
+        left_energy += left * left;
+        right_energy += right * right;
+    }
+    let energy = sqrt(left_energy * right_energy);
+    if energy == 0.0 {
+        return 0.0;
+    }
+    (cross / energy).clamp(-1.0, 1.0)
+}
+
+pub struct Delay<T: Num, const N: usize> {
+    write_head : WriteHead<T, N>,
+}
+
+impl<T: Num, const N: usize> Delay<T, N> {
+    pub fn new() -> Delay<T, N> {
+        Delay {write_head: WriteHead::new()}
+    }
+
+    /// Pushes `input` and mixes it with the sample `delay` pushes old.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delay` is not less than `N`.
+    pub fn process(&mut self, input: T, dry: T, wet: T, delay: usize) -> T where T: Add<Output = T> + Mul<Output = T> {
+        assert!(delay <= self.write_head.max_delay(), "delay must be less than the buffer length");
+        self.write_head.push(input);
+        let delayed = self.write_head.ago(delay + 1);
+        dry * input + wet * delayed
+    }
+}
+
+impl<T: Num, const N: usize> Default for Delay<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `read` must tap `write`'s whole active region. Heads over another buffer,
+/// and loop heads from `as_loop_readhead`, which only know their place inside
+/// the loop, have no position relative to the write head and are rejected.
+pub fn samples_available<T: Num, const N: usize>(read: &ReadHead<'_, T>, write: &WriteHead<T, N>) -> usize {
+    let whole = core::ptr::eq(read.buffer.as_ptr(), write.cells().as_ptr()) && read.size == write.active_len;
+    assert!(whole, "read head must tap the whole active region of this write head");
+    (write.head_position.get() + write.active_len - read.head_position % write.active_len) % write.active_len
+}
+
+pub struct CombFilter<T: Num, const N: usize> {
+    write_head : WriteHead<T, N>,
+    delay : usize,
+    feedback : T,
+    saturate : bool,
+}
+
+impl<T: Num, const N: usize> CombFilter<T, N> {
+    /// Feeds the output back `delay` samples later, scaled by `feedback`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delay` is zero or not less than `N`.
+    pub fn new(delay: usize, feedback: T) -> CombFilter<T, N> {
+        assert!(delay > 0 && delay < N, "delay must be non-zero and less than the buffer length");
+        CombFilter {write_head: WriteHead::new(), delay, feedback, saturate: false}
+    }
+
+    /// Like `new`, with the same panics, but soft-clips each output sample.
+    pub fn with_saturation(delay: usize, feedback: T) -> CombFilter<T, N> {
+        CombFilter {saturate: true, ..Self::new(delay, feedback)}
+    }
+
+    pub fn process(&mut self, input: T) -> T where T: Add<Output = T> + Mul<Output = T> {
+        let mut output = input + self.feedback * self.write_head.ago(self.delay);
+        if self.saturate {
+            let sample = output.to_normalized_f32();
+            output = T::from_normalized_f32(sample / (1.0 + sample.abs()));
+        }
+        self.write_head.push(output);
+        output
+    }
+}
+
+pub struct CombBank<T: Num, const N: usize, const K: usize> {
+    combs : [CombFilter<T, N>; K],
+}
+
+impl<T: Num, const N: usize, const K: usize> CombBank<T, N, K> {
+    pub fn new(combs: [CombFilter<T, N>; K]) -> CombBank<T, N, K> {
+        CombBank {combs}
+    }
+
+    pub fn process(&mut self, input: T) -> T where T: Add<Output = T> + Mul<Output = T> {
+        self.combs.iter_mut().fold(T::default_value(), |sum, comb| sum + comb.process(input))
+    }
+}
+
+pub fn would_alias<T: Num, const N: usize>(read: &ReadHead<'_, T>, write: &WriteHead<T, N>) -> bool {
+    core::ptr::eq(&read.buffer[read.head_position], &write.cells()[write.head_position.get()])
+}
+
+pub struct SmoothedDelay<T: Num, const N: usize> {
+    write_head : WriteHead<T, N>,
+    delay : f32,
+    target : f32,
+    smoothing : f32,
+}
+
+impl<T: Num, const N: usize> SmoothedDelay<T, N> {
+    pub fn new(delay: f32, smoothing: f32) -> SmoothedDelay<T, N> {
+        SmoothedDelay {write_head: WriteHead::new(), delay, target: delay, smoothing}
+    }
+
+    pub fn set_target(&mut self, target: f32) {
+        self.target = target;
+    }
+
+    pub fn delay(&self) -> f32 {
+        self.delay
+    }
+
+    pub fn process(&mut self, input: T) -> T {
+        self.write_head.push(input);
+        self.delay += (self.target - self.delay) * self.smoothing;
+        self.write_head.ago_lerp(self.delay + 1.0)
+    }
+}
+
+pub struct PitchShifter<T: Num, const N: usize> {
+    write_head : WriteHead<T, N>,
+    rate : f32,
+    delay : f32,
+}
+
+impl<T: Num, const N: usize> PitchShifter<T, N> {
+    pub fn new(rate: f32) -> PitchShifter<T, N> {
+        const { assert!(N > 2, "buffer must hold more than two samples") };
+        PitchShifter {write_head: WriteHead::new(), rate, delay: 0.0}
+    }
+
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate;
+    }
+
+    pub fn process(&mut self, input: T) -> T {
+        let window = (N - 2) as f32;
+        self.write_head.push(input);
+        self.delay += 1.0 - self.rate;
+        while self.delay >= window {
+            self.delay -= window;
+        }
+        while self.delay < 0.0 {
+            self.delay += window;
+        }
+
+        let mut output = 0.0;
+        for delay in [self.delay, (self.delay + window / 2.0) % window] {
+            let gain = 1.0 - (2.0 * delay / window - 1.0).abs();
+            output += gain * self.write_head.ago_lerp(delay + 1.0).to_normalized_f32();
+        }
+
+        T::from_normalized_f32(output)
+    }
+}
+
+pub struct PolyphaseDecimator<T: Num, const N: usize, const L: usize, const P: usize> {
+    write_head : WriteHead<T, N>,
+    phases : [[f32; P]; L],
+    phase : usize,
+}
+
+impl<T: Num, const N: usize, const L: usize, const P: usize> PolyphaseDecimator<T, N, L, P> {
+    pub fn new(coefficients: &[f32]) -> PolyphaseDecimator<T, N, L, P> {
+        const { assert!(L * P <= N, "buffer must hold L * P samples of history") };
+        assert_eq!(coefficients.len(), L * P, "decimator needs exactly L * P coefficients");
+        let mut phases = [[0.0; P]; L];
+        for (k, &coefficient) in coefficients.iter().enumerate() {
+            phases[k % L][k / L] = coefficient;
+        }
+        PolyphaseDecimator {write_head: WriteHead::new(), phases, phase: 0}
+    }
+
+    pub fn push(&mut self, input: T) -> Option<T> {
+        self.write_head.push(input);
+        let emit = self.phase == 0;
+        self.phase = (self.phase + 1) % L;
+        if !emit {
+            return None;
+        }
+
+        let mut output = 0.0;
+        for (p, bank) in self.phases.iter().enumerate() {
+            for (j, &coefficient) in bank.iter().enumerate() {
+                output += coefficient * self.write_head.ago(j * L + p + 1).to_normalized_f32();
+            }
+        }
+        Some(T::from_normalized_f32(output))
+    }
+}
+
+pub struct Writer<'a, T: AtomicSample> {
+    cells : &'a [T::Atomic],
+    head_position : &'a mut usize,
+    full : &'a mut bool,
+    active_len : usize,
+    frozen : bool,
+}
+
+impl<T: AtomicSample> Writer<'_, T> {
+    pub fn push(&mut self, element: T) {
+        if self.frozen {
+            return;
+        }
+        T::store(&self.cells[*self.head_position], element);
+        *self.head_position = (*self.head_position + 1) % self.active_len;
+        *self.full |= *self.head_position == 0;
+    }
+}
+
+pub struct Reader<'a, T: AtomicSample> {
+    cells : &'a [T::Atomic],
+    head_position : usize,
+}
+
+impl<T: AtomicSample> Clone for Reader<'_, T> {
+    fn clone(&self) -> Self {
+        Reader {cells: self.cells, head_position: self.head_position}
+    }
+}
+
+impl<T: AtomicSample> Iterator for Reader<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = T::load(&self.cells[self.head_position]);
+        self.head_position = (self.head_position + 1) % self.cells.len();
+
+        Some(sample)
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub struct OwnedWriter<T: AtomicSample> {
+    cells : Arc<[T::Atomic]>,
+    head_position : usize,
+    frozen : bool,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: AtomicSample> OwnedWriter<T> {
+    pub fn push(&mut self, element: T) {
+        if self.frozen {
+            return;
+        }
+        T::store(&self.cells[self.head_position], element);
+        self.head_position = (self.head_position + 1) % self.cells.len();
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub struct OwnedReader<T: AtomicSample> {
+    cells : Arc<[T::Atomic]>,
+    head_position : usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: AtomicSample> Clone for OwnedReader<T> {
+    fn clone(&self) -> Self {
+        OwnedReader {cells: self.cells.clone(), head_position: self.head_position}
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: AtomicSample> Iterator for OwnedReader<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = T::load(&self.cells[self.head_position]);
+        self.head_position = (self.head_position + 1) % self.cells.len();
+
+        Some(sample)
+    }
+}
+
+/// Pushes between a sample being written and `read` reaching it. A tap on
+/// the write position reads the oldest cell, so it reports `active_len`.
+pub fn reported_latency<T: Num, const N: usize>(read: &ReadHead<'_, T>, write: &WriteHead<T, N>) -> usize {
+    match samples_available(read, write) {
+        0 => write.active_len,
+        latency => latency,
+    }
+}
+
+pub fn interleave<T: Num, const N: usize>(left: &WriteHead<T, N>, right: &WriteHead<T, N>, out: &mut [T]) {
+    assert_eq!(out.len(), 2 * N);
+    for (n, frame) in out.chunks_exact_mut(2).enumerate() {
+        frame[0] = left.cells()[n].get();
+        frame[1] = right.cells()[n].get();
+    }
+}
+
+pub struct StereoDelay<T: Num, const N: usize> {
+    write_heads : [WriteHead<T, N>; 2],
+    lowpass : [f32; 2],
+    delay : usize,
+    feedback : f32,
+    damping : f32,
+    cross_feed : f32,
+}
+
+impl<T: Num, const N: usize> StereoDelay<T, N> {
+    /// Echoes each channel `delay` samples after its input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delay` is zero or not less than `N`.
+    pub fn new(delay: usize, feedback: f32, damping: f32, cross_feed: f32) -> StereoDelay<T, N> {
+        assert!(delay > 0 && delay < N, "delay must be non-zero and less than the buffer length");
+        StereoDelay {write_heads: [WriteHead::new(), WriteHead::new()], lowpass: [0.0; 2], delay, feedback, damping, cross_feed}
+    }
+
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback;
+    }
+
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = damping;
+    }
+
+    pub fn set_cross_feed(&mut self, cross_feed: f32) {
+        self.cross_feed = cross_feed;
+    }
+
+    pub fn process(&mut self, input: [T; 2]) -> [T; 2] {
+        let delayed = [self.write_heads[0].ago(self.delay), self.write_heads[1].ago(self.delay)];
+        for (lowpass, sample) in self.lowpass.iter_mut().zip(delayed) {
+            *lowpass += (1.0 - self.damping) * (sample.to_normalized_f32() - *lowpass);
+        }
+
+        for (channel, sample) in input.iter().enumerate() {
+            let feedback = (1.0 - self.cross_feed) * self.lowpass[channel] + self.cross_feed * self.lowpass[1 - channel];
+            let sample = sample.to_normalized_f32() + self.feedback * feedback;
+            self.write_heads[channel].push(T::from_normalized_f32(sample));
+        }
+
+        delayed
+    }
+}
+
+pub struct MovingAverage<T: Num, const N: usize> {
+    write_head : WriteHead<T, N>,
+    sum : f32,
+}
+
+impl<T: Num, const N: usize> MovingAverage<T, N> {
+    pub fn new() -> MovingAverage<T, N> {
+        MovingAverage {write_head: WriteHead::new(), sum: 0.0}
+    }
+
+    pub fn push(&mut self, x: T) -> T {
+        self.sum += x.to_normalized_f32() - self.write_head.current().to_normalized_f32();
+        self.write_head.push(x);
+        self.average()
+    }
+
+    pub fn average(&self) -> T {
+        T::from_normalized_f32(self.sum / N as f32)
+    }
+}
+
+impl<T: Num, const N: usize> Default for MovingAverage<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct RunningMax<T: Num, const N: usize> {
+    values : [T; N],
+    times : [usize; N],
+    front : usize,
+    len : usize,
+    time : usize,
+}
+
+impl<T: Num + PartialOrd, const N: usize> RunningMax<T, N> {
+    pub fn new() -> RunningMax<T, N> {
+        RunningMax {values: [T::default_value(); N], times: [0; N], front: 0, len: 0, time: 0}
+    }
+
+    pub fn push(&mut self, x: T) -> T {
+        if self.len > 0 && self.times[self.front] + N <= self.time {
+            self.front = (self.front + 1) % N;
+            self.len -= 1;
+        }
+        while self.len > 0 && self.values[(self.front + self.len - 1) % N] <= x {
+            self.len -= 1;
+        }
+        let back = (self.front + self.len) % N;
+        self.values[back] = x;
+        self.times[back] = self.time;
+        self.len += 1;
+        self.time += 1;
+        self.values[self.front]
+    }
+}
+
+impl<T: Num + PartialOrd, const N: usize> Default for RunningMax<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+
+    #[test]
+    pub fn read_head_is_generic() {
+        {
+            let write_head = WriteHead::<i32, 1>::new();
+            write_head.push(0);
+        }
+        {
+            let write_head = WriteHead::<f32, 1>::new();
+            write_head.push(0_f32);
+        }
+    }
+
+    #[test]
+    pub fn write_head_accepts_i16() {
+        let write_head = WriteHead::<i16, 1>::new();
+        write_head.push(0_i16);
+        assert_eq!(write_head.ago(1), 0);
+    }
+
+    #[test]
+    pub fn whole_sample_reads_keep_i32_precision() {
+        let write_head = WriteHead::<i32, 4>::new();
+        write_head.push(16777217);
+        write_head.push(-16777219);
+
+        let read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.read_lerp(0.0), 16777217);
+        assert_eq!(read_head.read_lerp(1.0), -16777219);
+        assert_eq!(read_head.read_cubic(1.0), -16777219);
+        assert_eq!(write_head.ago_lerp(1.0), -16777219);
+
+        write_head.push_blend(2147483647, 1.0);
+        write_head.push_blend(5, 0.0);
+        assert_eq!(write_head.ago(2), 2147483647);
+        assert_eq!(write_head.ago(1), 0);
+    }
+
+    #[test]
+    pub fn write_head_converts_between_i16_and_f32() {
+        let write_head = WriteHead::<i16, 3>::new();
+
+        write_head.push(i16::MAX);
+        write_head.push(i16::MIN);
+        write_head.push(0);
+
+        let converted = write_head.to_f32();
+        assert!((converted[0].get() - 1.0).abs() < 1e-4);
+        assert_eq!(converted[1].get(), -1.0);
+        assert_eq!(converted[2].get(), 0.0);
+
+        let round_trip = converted.to_i16();
+        assert_eq!(round_trip[0].get(), i16::MAX);
+        assert_eq!(round_trip[1].get(), i16::MIN);
+        assert_eq!(round_trip[2].get(), 0);
+    }
+
+    #[test]
+    pub fn f32_to_i16_clamps() {
+        let write_head = WriteHead::<f32, 2>::new();
+
+        write_head.push(2.0);
+        write_head.push(-2.0);
+
+        let converted = write_head.to_i16();
+        assert_eq!(converted[0].get(), i16::MAX);
+        assert_eq!(converted[1].get(), i16::MIN);
+    }
+
+    #[test]
+    pub fn read_head_with_delay_output_equals_write_head() {
+        let write_head = WriteHead::<i32, 5>::new();
+
+        write_head.push(1);
+       
+        for n in 0..4 {
+            let mut read_head = write_head.as_readhead(n);
+            for j in 0..4 {
+                let val = read_head.next().unwrap();
+                if j == n {
+                    assert_eq!(val, 1)
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn multiple_read_head_with_delay_output_equals_write_head() {
+        let write_head = WriteHead::<f32, 5>::new();
+
+        write_head.push(1.0);
+        
+        for n in 0..4 {
+            let mut read_head_1 = write_head.as_readhead(n);
+            let mut read_head_2 = write_head.as_readhead(n+1);
+            for j in 0..4 {
+                let val_1 = read_head_1.next().unwrap();
+                let val_2 = read_head_2.next().unwrap();
+                if j == n {
+                    assert_eq!(val_1, 1.0)
+                }
+                if j == (n + 1) {
+                    assert_eq!(val_2, 1.0)
+                }
+            }
+        }
+    }
+    
+    #[test]
+    pub fn read_head_is_circular() {
+        let write_head = WriteHead::<f32, 2>::new();
+        
+        write_head.push(1.0);
+
+        let mut read_head = write_head.as_readhead(0);
+        
+        assert_eq!(read_head.next().unwrap(), 1.0);
+        assert_eq!(read_head.next().unwrap(), 0.0);
+        assert_eq!(read_head.next().unwrap(), 1.0);
+    }
+
+    #[test]
+    pub fn read_head_index_operator() {
+        let write_head = WriteHead::<f32, 5>::new();
+
+        for n in 0..4 {
+            write_head.push(n as f32);
+        }
+
+        let read_head = write_head.as_readhead(0);
+        for n in 0..4 {
+            assert_eq!(read_head[n], n as f32);
+        }
+    }
+
+    #[test]
+    pub fn read_head_index_operator_is_circular() {
+        let write_head = WriteHead::<f32, 2>::new();
+
+        write_head.push(0.0);
+        write_head.push(1.0);
+
+        let read_head = write_head.as_readhead(0);
+        assert_eq!(read_head[0], 0.0);
+        assert_eq!(read_head[1], 1.0);
+        assert_eq!(read_head[2], 0.0);
+        assert_eq!(read_head[3], 1.0);
+        assert_eq!(read_head[4], 0.0);
+    }
+    
+    #[test]
+    pub fn write_head_is_circular() {
+        let write_head = WriteHead::<f32, 2>::new();
+        
+        write_head.push(0.0);
+        write_head.push(0.0);
+        write_head.push(1.0); // wraps around
+
+        let mut read_head = write_head.as_readhead(0);
+        
+        assert_eq!(read_head.next().unwrap(), 1.0);
+        assert_eq!(read_head.next().unwrap(), 0.0);
+    }
+    
+    #[test]
+    pub fn write_head_index_operator() {
+        let write_head = WriteHead::<f32, 5>::new();
+        
+        for n in 0..4 {
+            write_head[n].set(n as f32);
+        }
+
+        let mut read_head = write_head.as_readhead(0);
+        for n in 0..4 {
+            assert_eq!(read_head.next().unwrap(), n as f32);
+        }
+    }
+
+    #[test]
+    pub fn write_head_index_operator_is_circular() {
+        let write_head = WriteHead::<f32, 2>::new();
+        
+        write_head[0].set(0.0);
+        write_head[1].set(1.0);
+        write_head[2].set(2.0);
+        write_head[3].set(3.0);
+
+        let mut read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.next().unwrap(), 2.0);
+        assert_eq!(read_head.next().unwrap(), 3.0);
+    }
+
+    #[test]
+    pub fn resize_into_larger_buffer_preserves_samples() {
+        let write_head = WriteHead::<f32, 3>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+
+        let resized = write_head.resize_into::<5>();
+        assert_eq!(resized[0].get(), 1.0);
+        assert_eq!(resized[1].get(), 2.0);
+        assert_eq!(resized[2].get(), 0.0);
+        assert_eq!(resized[3].get(), 0.0);
+        assert_eq!(resized[4].get(), 0.0);
+
+        resized.push(3.0);
+        assert_eq!(resized[2].get(), 3.0);
+    }
+
+    #[test]
+    pub fn resize_into_keeps_recent_samples_after_wrap() {
+        let write_head = WriteHead::<f32, 3>::new();
+        for n in 1..5 {
+            write_head.push(n as f32);
+        }
+
+        let grown = write_head.resize_into::<5>();
+        assert_eq!(grown.ago(1), 4.0);
+        assert_eq!(grown.ago(2), 3.0);
+        assert_eq!(grown.ago(3), 2.0);
+        assert_eq!(grown.ago(4), 0.0);
+
+        let write_head = WriteHead::<f32, 4>::new();
+        for n in 1..4 {
+            write_head.push(n as f32);
+        }
+        let shrunk = write_head.resize_into::<2>();
+        assert_eq!(shrunk.ago(1), 3.0);
+        assert_eq!(shrunk.ago(2), 2.0);
+    }
+
+    #[test]
+    pub fn resize_into_keeps_active_len_and_frozen() {
+        let mut write_head = WriteHead::<f32, 4>::new();
+        write_head.set_active_len(3);
+        write_head.push(1.0);
+        write_head.set_frozen(true);
+
+        let resized = write_head.resize_into::<8>();
+        assert_eq!(resized.active_len(), 3);
+        assert!(resized.is_frozen());
+        assert_eq!(write_head.resize_into::<2>().active_len(), 2);
+    }
+
+    #[test]
+    pub fn read_head_scaled_applies_gain() {
+        let write_head = WriteHead::<f32, 3>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+        write_head.push(3.0);
+
+        let mut scaled = write_head.as_readhead(0).scaled(2.0);
+        assert_eq!(scaled.next().unwrap(), 2.0);
+        assert_eq!(scaled.next().unwrap(), 4.0);
+        assert_eq!(scaled.next().unwrap(), 6.0);
+        assert!(scaled.next().is_none());
+    }
+
+    #[test]
+    pub fn read_head_peek_back_returns_last_read() {
+        let write_head = WriteHead::<f32, 3>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+        write_head.push(3.0);
+
+        let mut read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.peek_back(), 3.0);
+
+        let val = read_head.next().unwrap();
+        assert_eq!(read_head.peek_back(), val);
+
+        let val = read_head.next().unwrap();
+        assert_eq!(read_head.peek_back(), val);
+    }
+
+    #[test]
+    pub fn linear_read_head_stops_at_write_position() {
+        let write_head = WriteHead::<f32, 3>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+
+        let mut read_head = write_head.as_linear_readhead();
+        assert_eq!(read_head.next(), Some(1.0));
+        assert_eq!(read_head.next(), Some(2.0));
+        assert_eq!(read_head.next(), None);
+        assert_eq!(read_head.next(), None);
+    }
+
+    #[test]
+    pub fn linear_read_head_stops_at_end() {
+        let write_head = WriteHead::<f32, 3>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+        write_head.push(3.0);
+
+        assert_eq!(write_head.as_linear_readhead().count(), 3);
+    }
+
+    #[test]
+    pub fn linear_read_head_starts_at_oldest_sample_once_full() {
+        let write_head = WriteHead::<i32, 3>::new();
+        for n in 1..5 {
+            write_head.push(n);
+        }
+
+        assert!(write_head.as_linear_readhead().eq([2, 3, 4]));
+        assert!(WriteHead::from_buffer([1, 2, 3]).as_linear_readhead().eq([1, 2, 3]));
+    }
+
+    #[test]
+    pub fn linear_read_head_on_cleared_buffer() {
+        let mut write_head = WriteHead::<i32, 3>::new();
+        for n in 1..5 {
+            write_head.push(n);
+        }
+
+        write_head.clear();
+        assert_eq!(write_head.as_linear_readhead().next(), None);
+
+        write_head.push(5);
+        assert!(write_head.as_linear_readhead().eq([5]));
+    }
+
+    #[test]
+    pub fn read_head_argmax() {
+        let write_head = WriteHead::<f32, 3>::new();
+
+        write_head.push(1.0);
+        write_head.push(5.0);
+        write_head.push(3.0);
+
+        let mut read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.argmax(3), 1);
+
+        read_head.next();
+        read_head.next();
+        assert_eq!(read_head.argmax(3), 2);
+    }
+
+    #[test]
+    pub fn correlate_two_read_heads() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+        write_head.push(3.0);
+        write_head.push(4.0);
+
+        let read_head_1 = write_head.as_readhead(0);
+        let read_head_2 = write_head.as_readhead(3);
+
+        // [1, 2, 3] . [2, 3, 4]
+        assert_eq!(correlate(&read_head_1, &read_head_2, 3), 20.0);
+        // [1, 2, 3, 4] . [2, 3, 4, 1]
+        assert_eq!(correlate(&read_head_1, &read_head_2, 4), 24.0);
+    }
+
+    #[test]
+    pub fn primed_write_head_reads_real_data() {
+        let mut write_head = WriteHead::<f32, 3>::new();
+
+        write_head.prime(&[1.0, 2.0, 3.0]);
+
+        let mut read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.next().unwrap(), 1.0);
+        assert_eq!(read_head.next().unwrap(), 2.0);
+        assert_eq!(read_head.next().unwrap(), 3.0);
+
+        write_head.push(4.0);
+        assert_eq!(write_head[0].get(), 4.0);
+    }
+
+    #[test]
+    pub fn prime_with_short_slice_positions_head_after_data() {
+        let mut write_head = WriteHead::<f32, 3>::new();
+
+        write_head.prime(&[1.0]);
+        write_head.push(2.0);
+
+        assert_eq!(write_head[0].get(), 1.0);
+        assert_eq!(write_head[1].get(), 2.0);
+    }
+
+    #[test]
+    pub fn write_head_ago() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+        write_head.push(3.0);
+
+        assert_eq!(write_head.ago(1), 3.0);
+        assert_eq!(write_head.ago(2), 2.0);
+        assert_eq!(write_head.ago(3), 1.0);
+        assert_eq!(write_head.ago(4), 0.0);
+    }
+
+    #[test]
+    pub fn write_head_index_operator_accumulates() {
+        let mut write_head = WriteHead::<f32, 4>::new();
+
+        for n in 0..3 {
+            write_head[n] += 1.0;
+        }
+        for n in 1..4 {
+            write_head[n] += 0.5;
+        }
+
+        assert_eq!(write_head[0], 1.0);
+        assert_eq!(write_head[1], 1.5);
+        assert_eq!(write_head[2], 1.5);
+        assert_eq!(write_head[3], 0.5);
+    }
+
+    #[test]
+    pub fn fill_noise_is_reproducible() {
+        let mut write_head_1 = WriteHead::<f32, 8>::new();
+        let mut write_head_2 = WriteHead::<f32, 8>::new();
+
+        write_head_1.fill_noise(1234);
+        write_head_2.fill_noise(1234);
+
+        for n in 0..8 {
+            assert_eq!(write_head_1[n].get(), write_head_2[n].get());
+            assert!(write_head_1[n].get() >= -1.0 && write_head_1[n].get() <= 1.0);
+        }
+
+        write_head_2.fill_noise(4321);
+        assert!((0..8).any(|n| write_head_1[n].get() != write_head_2[n].get()));
+    }
+
+    #[test]
+    pub fn delay_process_dry_returns_input() {
+        let mut delay = Delay::<f32, 4>::new();
+
+        for n in 1..8 {
+            assert_eq!(delay.process(n as f32, 1.0, 0.0, 2), n as f32);
+        }
+    }
+
+    #[test]
+    pub fn delay_process_wet_returns_delayed() {
+        let mut delay = Delay::<f32, 4>::new();
+
+        assert_eq!(delay.process(1.0, 0.0, 1.0, 2), 0.0);
+        assert_eq!(delay.process(2.0, 0.0, 1.0, 2), 0.0);
+        for n in 3..8 {
+            assert_eq!(delay.process(n as f32, 0.0, 1.0, 2), (n - 2) as f32);
+        }
+        assert_eq!(delay.process(8.0, 0.0, 1.0, 3), 5.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "delay must be less than the buffer length")]
+    pub fn delay_process_rejects_delay_of_buffer_length() {
+        Delay::<f32, 4>::new().process(1.0, 0.0, 1.0, 4);
+    }
+
+    #[test]
+    pub fn cloned_read_head_advances_independently() {
+        let write_head = WriteHead::<f32, 3>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+        write_head.push(3.0);
+
+        let mut read_head = write_head.as_readhead(0);
+        read_head.next();
+
+        let mut cloned = read_head.clone();
+        assert_eq!(cloned.next().unwrap(), 2.0);
+        assert_eq!(cloned.next().unwrap(), 3.0);
+        assert_eq!(read_head.next().unwrap(), 2.0);
+    }
+
+    #[test]
+    pub fn push_feedback_saturating_reports_clipping() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        assert!(!write_head.push_feedback_saturating(0.5, 0.9, 1, 1.0));
+        assert_eq!(write_head.ago(1), 0.5);
+
+        assert!(write_head.push_feedback_saturating(0.8, 0.9, 1, 1.0));
+        assert_eq!(write_head.ago(1), 1.0);
+
+        assert!(write_head.push_feedback_saturating(-2.0, 0.0, 1, 1.0));
+        assert_eq!(write_head.ago(1), -1.0);
+
+        assert!(!write_head.push_feedback_saturating(0.1, 0.5, 1, 1.0));
+        assert_eq!(write_head.ago(1), -0.4);
+    }
+
+    #[test]
+    pub fn read_head_read_lerp_interpolates() {
+        let write_head = WriteHead::<f32, 3>::new();
+
+        write_head.push(0.0);
+        write_head.push(1.0);
+        write_head.push(3.0);
+
+        let read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.read_lerp(0.5), 0.5);
+        assert_eq!(read_head.read_lerp(1.25), 1.5);
+        assert_eq!(read_head.read_lerp(2.5), 1.5);
+    }
+
+    #[test]
+    pub fn read_taps_lerp_with_integer_offsets_equals_read_taps() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        for n in 1..5 {
+            write_head.push(n as f32);
+        }
+
+        let read_head = write_head.as_readhead(0);
+        let expected = read_head.read_taps(&[(0, 0.5), (1, 0.25), (3, 1.0)]);
+        assert_eq!(expected, 0.5 + 0.5 + 4.0);
+        assert_eq!(read_head.read_taps_lerp(&[(0.0, 0.5), (1.0, 0.25), (3.0, 1.0)]), expected);
+        assert_eq!(read_head.read_taps_lerp(&[(0.5, 1.0), (1.5, 2.0)]), 1.5 + 5.0);
+    }
+
+    #[test]
+    pub fn write_head_current_is_next_cell_to_overwrite() {
+        let write_head = WriteHead::<f32, 3>::new();
+
+        write_head.push(1.0);
+        assert_eq!(write_head.current(), 0.0);
+
+        write_head.push(2.0);
+        write_head.push(3.0);
+        assert_eq!(write_head.current(), 1.0);
+
+        write_head.push(4.0);
+        assert_eq!(write_head.current(), 2.0);
+        assert_eq!(write_head.ago(1), 4.0);
+    }
+
+    #[test]
+    pub fn into_samples_yields_storage_order() {
+        assert!(WriteHead::from_buffer([1, 2, 3]).into_samples().eq([1, 2, 3]));
+
+        let write_head = WriteHead::from_buffer([1, 2, 3]);
+        write_head.push(4);
+        write_head.push(5);
+        assert!(write_head.into_samples().eq([4, 5, 3]));
+    }
+
+    #[test]
+    pub fn read_head_zero_crossings() {
+        let write_head = WriteHead::from_buffer([1.0, -1.0, 1.0, 1.0, 0.0]);
+
+        let read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.zero_crossings(3), 2);
+        assert_eq!(read_head.zero_crossings(5), 2);
+        assert_eq!(read_head.zero_crossings(1), 0);
+    }
+
+    #[test]
+    pub fn deinterleave_splits_channels() {
+        let write_head = WriteHead::from_buffer([1.0, -1.0, 2.0, -2.0, 3.0, -3.0]);
+
+        let mut left = [0.0; 3];
+        let mut right = [0.0; 3];
+        write_head.deinterleave(&mut left, &mut right);
+        assert_eq!(left, [1.0, 2.0, 3.0]);
+        assert_eq!(right, [-1.0, -2.0, -3.0]);
+
+        let mut interleaved = WriteHead::<f32, 6>::new();
+        interleaved.interleave(&left, &right);
+        assert!(interleaved.into_samples().eq(write_head.into_samples()));
+    }
+
+    #[test]
+    pub fn read_head_seek_smooth_crossfades() {
+        let write_head = WriteHead::from_buffer([0.0, 0.0, 0.0, 0.0, 3.0, 3.0, 3.0, 3.0]);
+
+        let mut read_head = write_head.as_readhead(0);
+        read_head.seek_smooth(4, 2);
+
+        assert_eq!(read_head.next().unwrap(), 1.0);
+        assert_eq!(read_head.next().unwrap(), 2.0);
+        assert_eq!(read_head.next().unwrap(), 3.0);
+        assert_eq!(read_head.next().unwrap(), 3.0);
+        assert_eq!(read_head.next().unwrap(), 0.0);
+    }
+
+    #[test]
+    pub fn samples_available_between_heads() {
+        let write_head = WriteHead::<f32, 5>::new();
+
+        let read_head = write_head.as_readhead(3);
+        assert_eq!(samples_available(&read_head, &write_head), 3);
+
+        let mut read_head = write_head.as_readhead(0);
+        assert_eq!(samples_available(&read_head, &write_head), 0);
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+        assert_eq!(samples_available(&read_head, &write_head), 2);
+
+        read_head.next();
+        assert_eq!(samples_available(&read_head, &write_head), 1);
+    }
+
+    #[test]
+    pub fn read_head_const_equals_read_head() {
+        let write_head = WriteHead::from_buffer([1.0, 2.0, 3.0, 4.0]);
+
+        let mut read_head = write_head.as_readhead_const::<3>();
+        let mut expected = write_head.as_readhead(3);
+        for _ in 0..4 {
+            assert_eq!(read_head.next(), expected.next());
+        }
+    }
+
+    #[test]
+    pub fn impulse_and_ones_constructors() {
+        let impulse = WriteHead::<f32, 3>::impulse();
+        let mut read_head = impulse.as_readhead(0);
+        assert_eq!(read_head.next().unwrap(), 1.0);
+        assert_eq!(read_head.next().unwrap(), 0.0);
+        assert_eq!(read_head.next().unwrap(), 0.0);
+
+        assert!(WriteHead::<i16, 3>::ones().into_samples().eq([1, 1, 1]));
+    }
+
+    #[test]
+    pub fn read_head_read_ramp_applies_linear_gain() {
+        let write_head = WriteHead::from_buffer([0.5; 5]);
+
+        let mut read_head = write_head.as_readhead(0);
+        let mut out = [0.0; 5];
+        read_head.read_ramp(&mut out, 0.0, 1.0);
+        assert_eq!(out, [0.0, 0.125, 0.25, 0.375, 0.5]);
+    }
+
+    #[test]
+    pub fn read_head_eq_window() {
+        let write_head = WriteHead::from_buffer([1, 2, 3]);
+
+        let mut read_head = write_head.as_readhead(0);
+        assert!(read_head.eq_window(&[1, 2, 3, 1]));
+        assert!(!read_head.eq_window(&[1, 3]));
+
+        read_head.next();
+        assert!(read_head.eq_window(&[2, 3, 1]));
+        assert!(read_head.eq_window(&[]));
+    }
+
+    #[test]
+    pub fn comb_filter_feeds_back_delayed_output() {
+        let mut comb = CombFilter::<f32, 4>::new(2, 0.5);
+
+        assert_eq!(comb.process(1.0), 1.0);
+        assert_eq!(comb.process(0.0), 0.0);
+        assert_eq!(comb.process(0.0), 0.5);
+        assert_eq!(comb.process(0.0), 0.0);
+        assert_eq!(comb.process(0.0), 0.25);
+    }
+
+    #[test]
+    pub fn comb_bank_of_one_equals_comb_filter() {
+        let mut comb = CombFilter::<f32, 8>::new(3, 0.7);
+        let mut bank = CombBank::new([CombFilter::<f32, 8>::new(3, 0.7)]);
+
+        for n in 0..16 {
+            let input = if n % 5 == 0 { 1.0 } else { 0.0 };
+            assert_eq!(bank.process(input), comb.process(input));
+        }
+    }
+
+    #[test]
+    pub fn comb_bank_sums_outputs() {
+        let mut bank = CombBank::new([CombFilter::<f32, 8>::new(2, 0.5), CombFilter::<f32, 8>::new(3, 0.5)]);
+
+        assert_eq!(bank.process(1.0), 2.0);
+        assert_eq!(bank.process(0.0), 0.0);
+        assert_eq!(bank.process(0.0), 0.5);
+        assert_eq!(bank.process(0.0), 0.5);
+    }
+
+    #[test]
+    pub fn q15_multiply() {
+        assert_eq!(Q15(16384) * Q15(16384), Q15(8192));
+        assert_eq!(Q15(-16384) * Q15(16384), Q15(-8192));
+        assert_eq!(Q15(i16::MIN) * Q15(i16::MIN), Q15(i16::MAX));
+        assert_eq!(Q15(i16::MAX) + Q15(1), Q15(i16::MAX));
+    }
+
+    #[test]
+    pub fn q15_lerp() {
+        assert_eq!(Q15(0).lerp(Q15(8192), Q15(16384)), Q15(4096));
+        assert_eq!(Q15(8192).lerp(Q15(0), Q15(8192)), Q15(6144));
+        assert_eq!(Q15(-8192).lerp(Q15(8192), Q15(0)), Q15(-8192));
+        assert_eq!(Q15(i16::MIN).lerp(Q15(i16::MAX), Q15(16384)), Q15(-1));
+        assert_eq!(Q15(i16::MAX).lerp(Q15(i16::MIN), Q15(i16::MAX)), Q15(-32767));
+    }
+
+    #[test]
+    pub fn q15_feedback_and_taps() {
+        let write_head = WriteHead::<Q15, 4>::new();
+
+        write_head.push(Q15(16384));
+        assert!(!write_head.push_feedback_saturating(Q15(0), Q15(16384), 1, Q15(i16::MAX)));
+        assert_eq!(write_head.ago(1), Q15(8192));
+
+        let read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.read_taps(&[(0, Q15(16384)), (1, Q15(16384))]), Q15(12288));
+    }
+
+    #[test]
+    pub fn read_storage_ignores_head_position() {
+        let mut write_head = WriteHead::from_buffer([1.0, 2.0, 3.0]);
+        write_head.seek(2);
+
+        let mut out = [0.0; 3];
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [1.0, 2.0, 3.0]);
+
+        let mut out = [0.0; 2];
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [1.0, 2.0]);
+    }
+
+    #[test]
+    pub fn add_slice_accumulates_with_wraparound() {
+        let mut write_head = WriteHead::<i32, 4>::new();
+        write_head.seek(2);
+
+        write_head.add_slice(&[1, 1, 1]);
+        write_head.add_slice(&[1, 1, 1]);
+        assert!(write_head.into_samples().eq([2, 0, 2, 2]));
+    }
+
+    #[test]
+    pub fn write_head_len_const() {
+        const LEN: usize = WriteHead::<f32, 8>::LEN;
+        let sized = [0.0_f32; LEN];
+        assert_eq!(sized.len(), 8);
+        assert_eq!(WriteHead::<i16, 3>::LEN, 3);
+    }
+
+    #[test]
+    pub fn read_head_is_silent() {
+        let write_head = WriteHead::from_buffer([0.001, -0.002, 0.0005, 0.5]);
+
+        let read_head = write_head.as_readhead(0);
+        assert!(read_head.is_silent(3, 0.01));
+        assert!(!read_head.is_silent(4, 0.01));
+        assert!(!read_head.is_silent(3, 0.001));
+    }
+
+    #[test]
+    pub fn read_head_read_modulated() {
+        let write_head = WriteHead::from_buffer([0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        let lfo_buffer = WriteHead::from_buffer([0.0, 0.5, 1.0, 0.5]);
+
+        let mut read_head = write_head.as_readhead(0);
+        let mut lfo = lfo_buffer.as_readhead(0);
+        for n in 0..4 {
+            assert_eq!(read_head.read_modulated(1.0, &mut lfo, 0.0), (n + 1) as f32);
+        }
+
+        let mut read_head = write_head.as_readhead(0);
+        let mut lfo = lfo_buffer.as_readhead(0);
+        assert_eq!(read_head.read_modulated(1.0, &mut lfo, 2.0), 1.0);
+        assert_eq!(read_head.read_modulated(1.0, &mut lfo, 2.0), 3.0);
+        assert_eq!(read_head.read_modulated(1.0, &mut lfo, 2.0), 5.0);
+    }
+
+    #[test]
+    pub fn reverse_flips_storage_order() {
+        let mut write_head = WriteHead::from_buffer([1, 2, 3]);
+
+        write_head.reverse();
+
+        let mut out = [0; 3];
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [3, 2, 1]);
+    }
+
+    #[test]
+    pub fn reverse_flips_active_region() {
+        let mut write_head = WriteHead::from_buffer([1, 2, 3, 4, 5]);
+        write_head.set_active_len(2);
+
+        write_head.reverse();
+
+        let mut out = [0; 5];
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [2, 1, 3, 4, 5]);
+    }
+
+    #[test]
+    pub fn buffer_mutators_act_on_active_region() {
+        let mut write_head = WriteHead::from_buffer([1.0, 2.0, 3.0, 4.0, 5.0]);
+        write_head.set_active_len(2);
+        let mut out = [0.0; 5];
+
+        write_head.apply_window(&[0.5, 2.0]);
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [0.5, 4.0, 3.0, 4.0, 5.0]);
+
+        write_head.clamp(1.0, 2.0);
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        write_head.decay(0.5);
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [0.5, 1.0, 3.0, 4.0, 5.0]);
+
+        write_head.fill_ramp(-1.0, -1.0);
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [-1.0, -2.0, 3.0, 4.0, 5.0]);
+
+        write_head.fill_noise(1234);
+        write_head.read_storage(&mut out);
+        assert!(out[..2].iter().all(|&sample| (-1.0..=1.0).contains(&sample)));
+        assert_eq!(out[2..], [3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "window must cover the active region")]
+    pub fn apply_window_rejects_full_length_window_on_shrunk_region() {
+        let mut write_head = WriteHead::<f32, 4>::new();
+        write_head.set_active_len(2);
+        write_head.apply_window(&[1.0; 4]);
+    }
+
+    #[test]
+    pub fn push_gated_holds_when_closed() {
+        let write_head = WriteHead::<i32, 3>::new();
+
+        write_head.push_gated(1, true);
+        write_head.push_gated(2, false);
+        write_head.push_gated(3, false);
+        assert_eq!(write_head[1].get(), 0);
+        assert_eq!(write_head[2].get(), 0);
+        assert_eq!(write_head.current(), 0);
+        assert_eq!(write_head.ago(1), 1);
+
+        write_head.push_gated(4, true);
+        assert_eq!(write_head[1].get(), 4);
+    }
+
+    #[test]
+    pub fn loop_read_head_cycles_sub_region() {
+        let write_head = WriteHead::from_buffer([1, 2, 3, 4, 5]);
+
+        let mut read_head = write_head.as_loop_readhead(1, 3);
+        for expected in [2, 3, 4, 2, 3, 4, 2] {
+            assert_eq!(read_head.next().unwrap(), expected);
+        }
+        assert_eq!(read_head[3].get(), 2);
+    }
+
+    #[test]
+    pub fn read_head_fir_frac_with_integer_delays_equals_fir() {
+        let write_head = WriteHead::from_buffer([1.0, 2.0, 3.0, 4.0]);
+
+        let read_head = write_head.as_readhead(0);
+        let expected = read_head.fir(&[0.5, 0.25, 0.0, 1.0]);
+        assert_eq!(expected, 0.5 + 0.5 + 4.0);
+        assert_eq!(read_head.fir_frac(&[(0.0, 0.5), (1.0, 0.25), (2.0, 0.0), (3.0, 1.0)]), expected);
+        assert_eq!(read_head.fir_frac(&[(0.5, 2.0)]), 3.0);
+    }
+
+    #[test]
+    pub fn process_block_passthrough() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        let input = [1.0, 2.0, 3.0];
+        let mut output = [0.0; 3];
+        write_head.process_block(&input, &mut output, |_, _, sample| sample);
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    pub fn process_block_drives_heads() {
+        let write_head = WriteHead::<f32, 2>::new();
+
+        let input = [1.0, 2.0, 3.0, 4.0];
+        let mut output = [0.0; 4];
+        write_head.process_block(&input, &mut output, |write_head, read_head, sample| {
+            let delayed = read_head.next().unwrap();
+            write_head.push(sample);
+            delayed
+        });
+        assert_eq!(output, [0.0, 0.0, 1.0, 2.0]);
+        assert_eq!(write_head.ago(1), 4.0);
+    }
+
+    #[test]
+    pub fn read_head_ratio_maps_to_offset() {
+        let write_head = WriteHead::from_buffer([1, 2, 3, 4]);
+
+        let mut read_head = write_head.as_readhead_ratio(0.5);
+        let mut expected = write_head.as_readhead(2);
+        assert!(read_head.eq_window(&[3, 4, 1, 2]));
+        for _ in 0..4 {
+            assert_eq!(read_head.next(), expected.next());
+        }
+
+        assert!(write_head.as_readhead_ratio(-1.0).eq_window(&[1, 2, 3, 4]));
+        assert!(write_head.as_readhead_ratio(2.0).eq_window(&[2, 3, 4, 1]));
+        assert!(write_head.as_readhead_ratio(1.0).eq_window(&[2, 3, 4, 1]));
+        assert!(write_head.as_readhead_ratio(0.9).eq_window(&[2, 3, 4, 1]));
+        assert!(write_head.as_readhead_ratio(0.2).eq_window(&[4, 1, 2, 3]));
+    }
+
+    #[test]
+    pub fn write_head_sum_samples() {
+        assert_eq!(WriteHead::from_buffer([1, 2, 3]).sum_samples(), 6);
+        assert_eq!(WriteHead::from_buffer([0.5, -1.5, 3.0]).sum_samples(), 2.0);
+    }
+
+    #[test]
+    pub fn normalize_scales_to_unit_peak() {
+        let mut write_head = WriteHead::from_buffer([0.5, -2.0, 1.0]);
+        write_head.normalize();
+        assert!(write_head.into_samples().eq([0.25, -1.0, 0.5]));
+
+        let mut write_head = WriteHead::from_buffer([0.5, -0.75, 0.0]);
+        write_head.normalize();
+        assert!(write_head.into_samples().eq([0.5, -0.75, 0.0]));
+
+        let mut write_head = WriteHead::<f32, 3>::new();
+        write_head.normalize();
+        assert!(write_head.into_samples().eq([0.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    pub fn allpass_read_head_integer_offset_matches_raw() {
+        let write_head = WriteHead::from_buffer([0.0, 1.0, 0.5, -0.5, 0.25]);
+
+        let read_head = AllpassReadHead::new(write_head.as_readhead(0), 1.0);
+        let expected = write_head.as_readhead(0);
+        for (n, sample) in read_head.take(5).enumerate() {
+            assert_eq!(sample, expected[n + 1].get());
+        }
+    }
+
+    #[test]
+    pub fn allpass_read_head_settles_on_constant() {
+        let write_head = WriteHead::from_buffer([0.5; 4]);
+
+        let sample = AllpassReadHead::new(write_head.as_readhead(0), 0.5).nth(31).unwrap();
+        assert!((sample - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    pub fn allpass_read_head_keeps_state_across_pushes() {
+        let write_head = WriteHead::<f32, 4>::new();
+        let mut read_head = AllpassReadHead::new(write_head.as_readhead(3), 0.5);
+
+        let mut sample = 0.0;
+        for _ in 0..32 {
+            write_head.push(0.5);
+            sample = read_head.next().unwrap();
+        }
+        assert!((sample - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    pub fn would_alias_detects_shared_cell() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        let mut read_head = write_head.as_readhead(0);
+        assert!(would_alias(&read_head, &write_head));
+
+        write_head.push(1.0);
+        assert!(!would_alias(&read_head, &write_head));
+
+        read_head.next();
+        assert!(would_alias(&read_head, &write_head));
+
+        let loop_head = write_head.as_loop_readhead(1, 2);
+        assert!(would_alias(&loop_head, &write_head));
+
+        let other = WriteHead::<f32, 4>::new();
+        assert!(!would_alias(&other.as_readhead(0), &WriteHead::<f32, 4>::new()));
+    }
+
+    #[test]
+    pub fn fill_ramp_writes_linear_data() {
+        let mut write_head = WriteHead::<i32, 4>::new();
+        write_head.fill_ramp(0, 1);
+        assert!(write_head.into_samples().eq([0, 1, 2, 3]));
+
+        let mut write_head = WriteHead::<f32, 3>::new();
+        write_head.fill_ramp(1.0, -0.5);
+        assert!(write_head.into_samples().eq([1.0, 0.5, 0.0]));
+    }
+
+    #[test]
+    pub fn highpass_read_head_decays_dc() {
+        let write_head = WriteHead::from_buffer([0.5; 4]);
+
+        let mut read_head = HighpassReadHead::new(write_head.as_readhead(0), 0.9);
+        let mut previous = read_head.next().unwrap();
+        assert_eq!(previous, 0.45);
+        for _ in 0..100 {
+            let sample = read_head.next().unwrap();
+            assert!(sample < previous);
+            previous = sample;
+        }
+        assert!(previous.abs() < 1e-4);
+    }
+
+    #[test]
+    pub fn write_head_behind() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+
+        assert_eq!(write_head.behind(1), 2.0);
+        assert_eq!(write_head.behind(2), 1.0);
+        assert_eq!(write_head.behind(3), 0.0);
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    pub fn to_heapless_matches_storage_order() {
+        let mut write_head = WriteHead::from_buffer([1, 2, 3]);
+        write_head.push(4);
+
+        let exported = write_head.to_heapless();
+        assert_eq!(exported.as_slice(), &[4, 2, 3]);
+
+        write_head.set_active_len(2);
+        assert_eq!(write_head.to_heapless().as_slice(), &[4, 2]);
+    }
+
+    #[test]
+    pub fn write_head_ago_lerp() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+
+        assert_eq!(write_head.ago_lerp(1.0), 2.0);
+        assert_eq!(write_head.ago_lerp(1.5), 1.5);
+        assert_eq!(write_head.ago_lerp(2.25), 0.75);
+    }
+
+    #[test]
+    pub fn smoothed_delay_ramps_toward_target() {
+        let mut delay = SmoothedDelay::<f32, 16>::new(2.0, 0.5);
+
+        delay.set_target(6.0);
+        let mut previous = delay.delay();
+        for _ in 0..4 {
+            delay.process(0.0);
+            assert!(delay.delay() > previous);
+            assert!(delay.delay() < 6.0);
+            assert!(delay.delay() - previous <= 2.0);
+            previous = delay.delay();
+        }
+        for _ in 0..32 {
+            delay.process(0.0);
+        }
+        assert!((delay.delay() - 6.0).abs() < 1e-3);
+    }
+
+    #[test]
+    pub fn smoothed_delay_reads_delayed_input() {
+        let mut delay = SmoothedDelay::<f32, 8>::new(2.0, 0.5);
+
+        for n in 1..6 {
+            let output = delay.process(n as f32);
+            assert_eq!(output, if n > 2 { (n - 2) as f32 } else { 0.0 });
+        }
+    }
+
+    #[test]
+    pub fn read_head_geometric_taps() {
+        let mut write_head = WriteHead::<f32, 16>::new();
+        write_head.fill_ramp(0.0, 1.0);
+
+        let read_head = write_head.as_readhead(0);
+        let mut taps = read_head.geometric_taps(1, 4, 0.5);
+        assert_eq!(taps.next(), Some(1.0));
+        assert_eq!(taps.next(), Some(2.0 * 0.5));
+        assert_eq!(taps.next(), Some(4.0 * 0.25));
+        assert_eq!(taps.next(), Some(8.0 * 0.125));
+        assert_eq!(taps.next(), None);
+
+        assert!(read_head.geometric_taps(3, 2, 0.5).eq([3.0, 3.0]));
+        assert_eq!(read_head.geometric_taps(1, 70, 1.0).count(), usize::BITS as usize);
+    }
+
+    #[test]
+    pub fn frozen_write_head_ignores_pushes() {
+        let mut write_head = WriteHead::from_buffer([1.0, 2.0, 3.0]);
+
+        write_head.set_frozen(true);
+        assert!(write_head.is_frozen());
+        for n in 0..5 {
+            write_head.push(n as f32);
+        }
+
+        let mut read_head = write_head.as_readhead(0);
+        for expected in [1.0, 2.0, 3.0, 1.0, 2.0, 3.0] {
+            assert_eq!(read_head.next().unwrap(), expected);
+        }
+
+        write_head.set_frozen(false);
+        write_head.push(4.0);
+        assert_eq!(write_head[0].get(), 4.0);
+    }
+
+    #[test]
+    pub fn read_head_autocorr_peaks_at_period() {
+        let write_head = WriteHead::from_buffer([1.0, 0.0, -1.0, 0.0, 1.0, 0.0, -1.0, 0.0]);
+
+        let read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.autocorr(0, 8), 4.0);
+        assert_eq!(read_head.autocorr(2, 8), -4.0);
+        assert_eq!(read_head.autocorr(4, 8), 4.0);
+        assert_eq!(read_head.autocorr(1, 8), 0.0);
+        assert!((1..8).all(|lag| read_head.autocorr(lag, 8) <= read_head.autocorr(4, 8)));
+    }
+
+    #[test]
+    pub fn read_head_read_taps_aligned() {
+        let write_head = WriteHead::from_buffer([1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let read_head = write_head.as_readhead(1);
+        let taps = read_head.read_taps_aligned([0, 1, 3, 6]);
+        assert_eq!(taps.0, [5.0, 1.0, 3.0, 1.0]);
+        assert_eq!(core::mem::align_of_val(&taps), 16);
+        assert_eq!(&taps.0 as *const _ as usize % 16, 0);
+    }
+
+    #[test]
+    pub fn pitch_shifter_unity_rate_passes_signal() {
+        let mut shifter = PitchShifter::<f32, 10>::new(1.0);
+
+        for n in 0..32_usize {
+            let output = shifter.process(n as f32);
+            let expected = n.saturating_sub(4) as f32;
+            assert!((output - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    pub fn pitch_shifter_crossfade_keeps_constant_level() {
+        let mut shifter = PitchShifter::<f32, 18>::new(1.5);
+
+        for _ in 0..18 {
+            shifter.process(0.5);
+        }
+        for _ in 0..64 {
+            assert!((shifter.process(0.5) - 0.5).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    pub fn linear_read_head_next_checked_reports_underrun() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        write_head.push(1.0);
+        let mut read_head = write_head.as_linear_readhead();
+        assert_eq!(read_head.next_checked(&write_head), Ok(1.0));
+        assert_eq!(read_head.next_checked(&write_head), Err(Underrun));
+
+        write_head.push(2.0);
+        assert_eq!(read_head.next_checked(&write_head), Ok(2.0));
+        assert_eq!(read_head.next_checked(&write_head), Err(Underrun));
+    }
+
+    #[test]
+    pub fn linear_read_head_next_checked_follows_live_writes() {
+        let write_head = WriteHead::<f32, 4>::new();
+        let mut read_head = write_head.as_linear_readhead();
+        assert_eq!(read_head.next_checked(&write_head), Err(Underrun));
+
+        write_head.push(1.0);
+        write_head.push(2.0);
+        assert_eq!(read_head.next_checked(&write_head), Ok(1.0));
+
+        write_head.push(3.0);
+        assert_eq!(read_head.next_checked(&write_head), Ok(2.0));
+        assert_eq!(read_head.next_checked(&write_head), Ok(3.0));
+        assert_eq!(read_head.next_checked(&write_head), Err(Underrun));
+
+        write_head.push(4.0);
+        assert_eq!(read_head.next_checked(&write_head), Ok(4.0));
+        assert_eq!(read_head.next_checked(&write_head), Err(Underrun));
+    }
+
+    #[test]
+    #[should_panic(expected = "linear read head must come from this write head")]
+    pub fn linear_read_head_next_checked_rejects_other_write_head() {
+        let write_head = WriteHead::<f32, 3>::new();
+        let other = WriteHead::<f32, 8>::new();
+        for _ in 0..5 {
+            other.push(1.0);
+        }
+
+        let mut read_head = write_head.as_linear_readhead();
+        let _ = read_head.next_checked(&other);
+    }
+
+    #[test]
+    pub fn polyphase_decimator_matches_naive_fir_then_downsample() {
+        let coefficients = [0.1, 0.2, 0.3, 0.2, 0.1, -0.1];
+        let mut signal = WriteHead::<f32, 24>::new();
+        signal.fill_noise(7);
+
+        let mut decimator = PolyphaseDecimator::<f32, 8, 3, 2>::new(&coefficients);
+        let mut outputs = 0;
+        for n in 0..24 {
+            let output = decimator.push(signal[n].get());
+            if n % 3 != 0 {
+                assert!(output.is_none());
+                continue;
+            }
+            let mut expected = 0.0;
+            for (k, &coefficient) in coefficients.iter().enumerate() {
+                if k <= n {
+                    expected += coefficient * signal[n - k].get();
+                }
+            }
+            assert!((output.unwrap() - expected).abs() < 1e-5);
+            outputs += 1;
+        }
+        assert_eq!(outputs, 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "decimator needs exactly L * P coefficients")]
+    pub fn polyphase_decimator_rejects_wrong_coefficient_count() {
+        PolyphaseDecimator::<f32, 8, 3, 2>::new(&[0.1, 0.2, 0.3]);
+    }
+
+    #[test]
+    pub fn from_tail_keeps_last_samples() {
+        let write_head = WriteHead::<i32, 3>::from_tail(&[1, 2, 3, 4, 5]);
+        assert!(write_head.into_samples().eq([3, 4, 5]));
+
+        let write_head = WriteHead::<i32, 3>::from_tail(&[1, 2]);
+        assert_eq!(write_head.ago(1), 2);
+        write_head.push(6);
+        assert!(write_head.into_samples().eq([1, 2, 6]));
+    }
+
+    #[test]
+    pub fn read_head_read_enveloped() {
+        let write_head = WriteHead::from_buffer([1.0, 2.0, 3.0]);
+
+        let mut read_head = write_head.as_readhead(0);
+        let mut out = [0.0; 4];
+        read_head.read_enveloped(&[1.0; 4], &mut out);
+        assert_eq!(out, [1.0, 2.0, 3.0, 1.0]);
+
+        let mut out = [0.0; 2];
+        read_head.read_enveloped(&[0.5, 0.0], &mut out);
+        assert_eq!(out, [1.0, 0.0]);
+    }
+
+    #[test]
+    pub fn split_heads_exchange_data_across_threads() {
+        let mut write_head = WriteHead::<f32, 4>::new();
+        let (mut writer, reader) = write_head.split(0);
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                for n in 1..=100 {
+                    writer.push(n as f32);
+                }
+            });
+
+            let readers = [reader.clone(), reader];
+            for mut reader in readers {
+                scope.spawn(move || {
+                    for _ in 0..100 {
+                        let sample = reader.next().unwrap();
+                        assert!(sample.fract() == 0.0 && (0.0..=100.0).contains(&sample));
+                    }
+                });
+            }
+        });
+
+        assert!(write_head.into_samples().eq([97.0, 98.0, 99.0, 100.0]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    pub fn owned_heads_exchange_data_across_threads() {
+        let (mut writer, reader) = WriteHead::<f32, 4>::new().into_heads();
+        let tail = reader.clone();
+
+        let writer_thread = std::thread::spawn(move || {
+            for n in 1..=100 {
+                writer.push(n as f32);
+            }
+        });
+        let reader_threads = [reader.clone(), reader].map(|mut reader| {
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    let sample = reader.next().unwrap();
+                    assert!(sample.fract() == 0.0 && (0.0..=100.0).contains(&sample));
+                }
+            })
+        });
+
+        writer_thread.join().unwrap();
+        for reader_thread in reader_threads {
+            reader_thread.join().unwrap();
+        }
+        assert!(tail.take(4).eq([97.0, 98.0, 99.0, 100.0]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    pub fn owned_reader_starts_on_oldest_sample() {
+        let mut write_head = WriteHead::from_buffer([1, 2, 3, 4]);
+        write_head.push(5);
+        write_head.set_frozen(true);
+
+        let (mut writer, reader) = write_head.into_heads();
+        writer.push(9);
+        assert!(reader.take(4).eq([2, 3, 4, 5]));
+    }
+
+    #[test]
+    pub fn split_writer_honours_frozen() {
+        let mut write_head = WriteHead::from_buffer([1, 2, 3]);
+        write_head.set_frozen(true);
+
+        let (mut writer, mut reader) = write_head.split(0);
+        writer.push(9);
+        assert_eq!(reader.next(), Some(1));
+        assert_eq!(write_head.current(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "delay must be non-zero and less than the buffer length")]
+    pub fn comb_filter_rejects_delay_of_buffer_length() {
+        CombFilter::<f32, 4>::new(4, 0.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "delay must be non-zero and less than the buffer length")]
+    pub fn saturating_comb_filter_rejects_zero_delay() {
+        CombFilter::<f32, 4>::with_saturation(0, 0.5);
+    }
+
+    #[test]
+    pub fn saturating_comb_filter_stays_bounded() {
+        let mut comb = CombFilter::<f32, 4>::new(2, 1.5);
+        let mut saturating = CombFilter::<f32, 4>::with_saturation(2, 1.5);
+
+        let mut peak = 0.0_f32;
+        let mut saturating_peak = 0.0_f32;
+        for n in 0..200 {
+            let input = if n == 0 { 1.0 } else { 0.0 };
+            peak = peak.max(comb.process(input).abs());
+            saturating_peak = saturating_peak.max(saturating.process(input).abs());
+        }
+        assert!(peak > 1e6);
+        assert!(saturating_peak < 1.0);
+        assert!(saturating.process(0.0).abs() > 0.1);
+    }
+
+    #[test]
+    pub fn write_head_mean() {
+        assert_eq!(WriteHead::from_buffer([2.0, 4.0]).mean(), 3.0);
+        assert_eq!(WriteHead::from_buffer([i16::MIN, 0]).mean(), -0.5);
+
+        let mut write_head = WriteHead::from_buffer([2.0, 4.0, 9.0]);
+        write_head.set_active_len(2);
+        assert_eq!(write_head.mean(), 3.0);
+    }
+
+    #[test]
+    pub fn read_head_read_cubic() {
+        let write_head = WriteHead::from_buffer([0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.read_cubic(2.0), 2.0);
+        assert_eq!(read_head.read_cubic(2.5), 2.5);
+        assert!((read_head.read_cubic(1.25) - 1.25).abs() < 1e-6);
+    }
+
+    #[test]
+    pub fn read_head_read_cubic_oversampled() {
+        let write_head = WriteHead::<f32, 32>::new();
+        for n in 0..32 {
+            write_head[n].set((n as f32 * 0.2).sin() * 0.5);
+        }
+
+        let read_head = write_head.as_readhead(0);
+        for offset in [4.0, 7.3, 10.5, 20.9] {
+            let cubic = read_head.read_cubic(offset);
+            assert_eq!(read_head.read_cubic_oversampled(offset, 1), cubic);
+            for os in [2, 4, 8] {
+                assert!((read_head.read_cubic_oversampled(offset, os) - cubic).abs() < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    pub fn read_head_frames() {
+        let write_head = WriteHead::from_buffer([1.0, -1.0, 2.0, -2.0]);
+
+        let mut frames = write_head.as_readhead(0).frames();
+        assert_eq!(frames.next(), Some([1.0, -1.0]));
+        assert_eq!(frames.next(), Some([2.0, -2.0]));
+        assert_eq!(frames.next(), None);
+    }
+
+    #[test]
+    pub fn write_head_clamp() {
+        let mut write_head = WriteHead::from_buffer([-3, -1, 0, 2, 5]);
+
+        write_head.clamp(-2, 2);
+        assert!(write_head.into_samples().eq([-2, -1, 0, 2, 2]));
+    }
+
+    #[test]
+    pub fn read_head_signed_delay() {
+        let write_head = WriteHead::from_buffer([1, 2, 3, 4, 5]);
+
+        assert!(write_head.as_readhead_signed(2).eq_window(&[4, 5, 1]));
+        assert!(write_head.as_readhead_signed(0).eq_window(&[1, 2, 3]));
+        assert!(write_head.as_readhead_signed(-2).eq_window(&[3, 4, 5]));
+        assert!(write_head.as_readhead_signed(-7).eq_window(&[3, 4, 5]));
+        assert!(write_head.as_readhead_signed(7).eq_window(&[4, 5, 1]));
+
+        write_head.push(10);
+        write_head.push(20);
+        assert!(write_head.as_readhead_signed(1).eq_window(&[20, 3, 4]));
+        assert!(write_head.as_readhead_signed(0).eq_window(&[3, 4, 5]));
+        assert!(write_head.as_readhead_signed(-1).eq_window(&[4, 5, 10]));
+    }
+
+    #[test]
+    pub fn write_head_nonzero_count() {
+        let write_head = WriteHead::<f32, 5>::new();
+        assert_eq!(write_head.nonzero_count(), 0);
+
+        write_head.push(1.0);
+        write_head.push(-1.0);
+        assert_eq!(write_head.nonzero_count(), 2);
+
+        write_head.push(0.0);
+        assert_eq!(write_head.nonzero_count(), 2);
+    }
+
+    #[test]
+    pub fn fractional_read_head_advances_by_fractions() {
+        let write_head = WriteHead::from_buffer([0.0, 1.0, 2.0, 3.0]);
+
+        let mut read_head = FractionalReadHead::new(write_head.as_readhead(0));
+        assert_eq!(read_head.read(), 0.0);
+
+        read_head.advance_frac(0.5);
+        assert_eq!(read_head.read(), 0.5);
+
+        read_head.advance_frac(0.5);
+        assert_eq!(read_head.read(), 1.0);
+
+        read_head.advance_frac(2.25);
+        assert_eq!(read_head.read(), 2.25);
+
+        read_head.advance_frac(1.5);
+        assert_eq!(read_head.read(), 0.75);
+    }
+
+    #[test]
+    pub fn fractional_read_head_advances_backwards() {
+        let write_head = WriteHead::from_buffer([0.0, 10.0, 0.0, 0.0]);
+
+        let mut read_head = FractionalReadHead::new(write_head.as_readhead(3));
+        assert_eq!(read_head.read(), 10.0);
+
+        read_head.advance_frac(-1.0);
+        assert_eq!(read_head.read(), 0.0);
+
+        read_head.advance_frac(-3.0);
+        read_head.advance_frac(-0.25);
+        assert_eq!(read_head.read(), 7.5);
+
+        read_head.advance_frac(-0.75);
+        assert_eq!(read_head.read(), 0.0);
+    }
+
+    #[test]
+    pub fn write_head_to_bytes() {
+        let write_head = WriteHead::<i16, 3>::from_buffer([1, -2, 0x1234]);
+
+        let mut out = [0; 6];
+        write_head.to_bytes_le(&mut out).unwrap();
+        assert_eq!(out, [0x01, 0x00, 0xfe, 0xff, 0x34, 0x12]);
+
+        write_head.to_bytes_be(&mut out).unwrap();
+        assert_eq!(out, [0x00, 0x01, 0xff, 0xfe, 0x12, 0x34]);
+
+        let mut out = [0; 4];
+        WriteHead::from_buffer([1.0_f32]).to_bytes_le(&mut out).unwrap();
+        assert_eq!(out, 1.0_f32.to_le_bytes());
+
+        let mut out = [0; 5];
+        assert_eq!(write_head.to_bytes_le(&mut out), Err(MultitapError::InvalidByteLength));
+        assert_eq!(write_head.to_bytes_be(&mut out), Err(MultitapError::InvalidByteLength));
+    }
+
+    #[test]
+    pub fn from_bytes_le_round_trip() {
+        let write_head = WriteHead::<i16, 3>::from_buffer([1, -2, 0x1234]);
+
+        let mut bytes = [0; 6];
+        write_head.to_bytes_le(&mut bytes).unwrap();
+        let parsed = WriteHead::<i16, 3>::from_bytes_le(&bytes).unwrap();
+        assert!(parsed.into_samples().eq([1, -2, 0x1234]));
+
+        let mut bytes = [0; 8];
+        WriteHead::from_buffer([0.5_f32, -0.25]).to_bytes_le(&mut bytes).unwrap();
+        assert!(WriteHead::<f32, 2>::from_bytes_le(&bytes).unwrap().into_samples().eq([0.5, -0.25]));
+    }
+
+    #[test]
+    pub fn from_bytes_le_validates_length() {
+        assert_eq!(WriteHead::<i16, 3>::from_bytes_le(&[0; 5]).err(), Some(MultitapError::InvalidByteLength));
+        assert_eq!(WriteHead::<i16, 3>::from_bytes_le(&[0; 8]).err(), Some(MultitapError::InvalidByteLength));
+    }
+
+    #[test]
+    pub fn tremolo_read_head() {
+        let write_head = WriteHead::from_buffer([1.0, 0.5, -1.0, 0.25]);
+
+        let read_head = TremoloReadHead::new(write_head.as_readhead(0), 0.1, 0.0);
+        for (n, sample) in read_head.take(8).enumerate() {
+            assert_eq!(sample, write_head[n].get());
+        }
+
+        let write_head = WriteHead::from_buffer([1.0; 4]);
+        let expected = [1.0, 0.75, 0.5, 0.75, 1.0, 0.75];
+        assert!(TremoloReadHead::new(write_head.as_readhead(0), 0.25, 0.5).take(6).eq(expected));
+        assert!(TremoloReadHead::new(write_head.as_readhead(0), -0.25, 0.5).take(6).eq(expected));
+    }
+
+    #[test]
+    pub fn write_head_max_delay() {
+        assert_eq!(WriteHead::<f32, 3>::new().max_delay(), 2);
+        assert_eq!(WriteHead::<f32, 1>::new().max_delay(), 0);
+    }
+
+    #[test]
+    pub fn push_blend_mixes_with_existing() {
+        let write_head = WriteHead::from_buffer([0.5, 0.5, 0.5]);
+
+        write_head.push_blend(1.0, 1.0);
+        write_head.push_blend(1.0, 0.0);
+        write_head.push_blend(1.0, 0.5);
+        assert_eq!(write_head.current(), 1.0);
+        assert!(write_head.into_samples().eq([1.0, 0.5, 0.75]));
+    }
+
+    #[test]
+    pub fn reported_latency_of_tap() {
+        let mut write_head = WriteHead::<f32, 256>::new();
+
+        let mut read_head = write_head.as_readhead_signed(100);
+        assert_eq!(reported_latency(&read_head, &write_head), 100);
+
+        for _ in 0..300 {
+            write_head.push(0.0);
+            read_head.next();
+        }
+        assert_eq!(reported_latency(&read_head, &write_head), 100);
+
+        write_head.push(0.0);
+        assert_eq!(reported_latency(&read_head, &write_head), 101);
+        assert_eq!(reported_latency(&write_head.as_readhead_signed(100), &write_head), 100);
+        assert_eq!(reported_latency(&write_head.as_colocated_readhead(), &write_head), 256);
+
+        write_head.set_active_len(100);
+        assert_eq!(reported_latency(&write_head.as_colocated_readhead(), &write_head), 100);
+    }
+
+    #[test]
+    pub fn read_head_read_spread() {
+        let mut write_head = WriteHead::<f32, 16>::new();
+        write_head.fill_ramp(0.0, 1.0);
+
+        let mut out = [0.0; 4];
+        write_head.as_readhead(0).read_spread(4, &mut out);
+        assert_eq!(out, [0.0, 4.0, 8.0, 12.0]);
+
+        let mut out = [0.0; 3];
+        write_head.as_readhead(0).read_spread(3, &mut out);
+        assert!(out.windows(2).all(|pair| pair[1] - pair[0] == 5.0));
+
+        write_head.as_readhead(0).read_spread(0, &mut []);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot spread more taps than the head has samples")]
+    pub fn read_spread_rejects_more_taps_than_samples() {
+        let write_head = WriteHead::<f32, 2>::new();
+        write_head.as_readhead(0).read_spread(3, &mut [0.0; 3]);
+    }
+
+    #[test]
+    pub fn push_with_wrap_reports_lap_boundary() {
+        let mut write_head = WriteHead::<f32, 3>::new();
+
+        let wraps: [bool; 7] = core::array::from_fn(|n| write_head.push_with_wrap(n as f32));
+        assert_eq!(wraps, [false, false, true, false, false, true, false]);
+
+        write_head.set_frozen(true);
+        write_head.seek(0);
+        assert!(!write_head.push_with_wrap(1.0));
+    }
+
+    #[test]
+    pub fn read_head_mix_into_sums_taps() {
+        let write_head = WriteHead::from_buffer([1.0, 2.0, 3.0, 4.0]);
+
+        let mut out = [0.0; 4];
+        write_head.as_readhead(0).mix_into(&mut out, 1.0);
+        write_head.as_readhead(1).mix_into(&mut out, 0.5);
+        assert_eq!(out, [3.0, 2.5, 4.0, 5.5]);
+    }
+
+    #[test]
+    pub fn rewind_to_start_keeps_data() {
+        let mut write_head = WriteHead::<i32, 4>::new();
+        write_head.prime(&[1, 2, 3]);
+
+        write_head.rewind_to_start();
+        write_head.push(9);
+        assert!(write_head.into_samples().eq([9, 2, 3, 0]));
+    }
+
+    #[test]
+    pub fn active_len_sets_wrap_point() {
+        let mut write_head = WriteHead::<f32, 5>::new();
+        write_head.set_active_len(2);
+
+        assert!(!write_head.push_with_wrap(1.0));
+        assert!(write_head.push_with_wrap(2.0));
+        write_head.push(3.0);
+
+        let mut storage = [0.0; 5];
+        write_head.read_storage(&mut storage);
+        assert_eq!(storage, [3.0, 2.0, 0.0, 0.0, 0.0]);
+        assert_eq!(write_head.ago(1), 3.0);
+        assert_eq!(write_head.ago(2), 2.0);
+        assert_eq!(write_head.max_delay(), 1);
+
+        let mut read_head = write_head.as_readhead(0);
+        for expected in [3.0, 2.0, 3.0, 2.0] {
+            assert_eq!(read_head.next().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    pub fn shrinking_active_len_wraps_head() {
+        let mut write_head = WriteHead::<f32, 5>::new();
+        write_head.seek(4);
+
+        write_head.set_active_len(3);
+        assert_eq!(write_head.active_len(), 3);
+        write_head.push(1.0);
+        assert_eq!(write_head[1].get(), 1.0);
+
+        write_head.set_active_len(0);
+        assert_eq!(write_head.active_len(), 1);
+        write_head.set_active_len(8);
+        assert_eq!(write_head.active_len(), 5);
+    }
+
+    #[test]
+    pub fn read_head_take_while_read() {
+        let write_head = WriteHead::from_buffer([0.1, 0.2, 0.3, 0.9, 0.1]);
+
+        let mut read_head = write_head.as_readhead(0);
+        let mut out = [0.0; 5];
+        assert_eq!(read_head.take_while_read(|sample| sample < 0.5, &mut out), 3);
+        assert_eq!(out[..3], [0.1, 0.2, 0.3]);
+        assert_eq!(read_head.next().unwrap(), 0.9);
+
+        let mut out = [0.0; 2];
+        assert_eq!(read_head.take_while_read(|_| true, &mut out), 2);
+        assert_eq!(out, [0.1, 0.1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "delay must be non-zero and less than the buffer length")]
+    pub fn stereo_delay_rejects_delay_of_buffer_length() {
+        StereoDelay::<f32, 4>::new(4, 0.5, 0.0, 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "delay must be non-zero and less than the buffer length")]
+    pub fn stereo_delay_rejects_zero_delay() {
+        StereoDelay::<f32, 4>::new(0, 0.5, 0.0, 0.0);
+    }
+
+    #[test]
+    pub fn stereo_delay_cross_feeds_damped_echo() {
+        let mut delay = StereoDelay::<f32, 16>::new(4, 0.8, 0.5, 1.0);
+
+        let mut left = [0.0; 16];
+        let mut right = [0.0; 16];
+        for n in 0..16 {
+            let input = if n == 0 { [1.0, 0.0] } else { [0.0, 0.0] };
+            [left[n], right[n]] = delay.process(input);
+        }
+
+        assert_eq!(left[4], 1.0);
+        assert!(left[..12].iter().enumerate().all(|(n, &sample)| n == 4 || sample == 0.0));
+        assert!(right[..8].iter().all(|&sample| sample == 0.0));
+
+        let peak = right.iter().fold(0.0_f32, |peak, &sample| peak.max(sample));
+        assert!(peak > 0.0 && peak < 0.8);
+        assert!(right[8..12].iter().filter(|&&sample| sample > 0.0).count() > 1);
+    }
+
+    #[test]
+    pub fn read_heads_observe_writes_while_alive() {
+        let write_head = WriteHead::<f32, 3>::new();
+        let mut read_head = write_head.as_readhead(0);
+        let mut linear_head = write_head.as_linear_readhead();
+
+        write_head[0].set(1.0);
+        write_head.push(2.0);
+        assert_eq!(read_head[0].get(), 2.0);
+        assert_eq!(read_head[3].get(), 2.0);
+        assert_eq!(linear_head.next_checked(&write_head), Ok(2.0));
+
+        write_head[1].set(3.0);
+        assert_eq!(read_head.next().unwrap(), 2.0);
+        assert_eq!(read_head.next().unwrap(), 3.0);
+    }
+
+    #[test]
+    pub fn data_mut_writes_reach_read_heads() {
+        let write_head = WriteHead::<f32, 3>::new();
+        unsafe {
+            let data = write_head.data_mut();
+            data[0] = 1.0;
+            data[2] = 3.0;
+        }
+
+        let mut read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.next().unwrap(), 1.0);
+        assert_eq!(read_head.next().unwrap(), 0.0);
+        assert_eq!(read_head.next().unwrap(), 3.0);
+    }
+
+    #[test]
+    pub fn moving_average_of_constant() {
+        let mut average = MovingAverage::<f32, 4>::new();
+
+        assert_eq!(average.push(0.5), 0.125);
+        for _ in 0..7 {
+            average.push(0.5);
+        }
+        assert_eq!(average.average(), 0.5);
+
+        assert_eq!(average.push(-0.5), 0.25);
+    }
+
+    #[test]
+    pub fn read_head_diff() {
+        let write_head = WriteHead::from_buffer([1, 4, 9, 16]);
+
+        let read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.diff(3, 1), 12);
+        assert_eq!(read_head.diff(0, 2), -8);
+        assert_eq!(write_head.as_readhead(1).diff(0, 1), 15);
+    }
+
+    #[test]
+    pub fn read_head_read_bitcrush() {
+        let write_head = WriteHead::from_buffer([0.3, -0.2, 0.9, -1.0, 0.0]);
+
+        let mut read_head = write_head.as_readhead(0);
+        for _ in 0..5 {
+            let crushed = read_head.read_bitcrush(1);
+            assert!(crushed == 1.0 || crushed == -1.0);
+            read_head.next();
+        }
+        assert!((read_head.read_bitcrush(2) - 1.0 / 3.0).abs() < 1e-6);
+        assert!((read_head.read_bitcrush(24) - 0.3).abs() < 1e-6);
+
+        let write_head = WriteHead::from_buffer([0x1234_i16, -0x1234]);
+        let read_head = write_head.as_readhead(0);
+        assert_eq!(read_head.read_bitcrush(8), 0x1200);
+        assert_eq!(read_head.read_bitcrush(16), 0x1234);
+        assert_eq!(write_head.as_readhead(1).read_bitcrush(1), i16::MIN);
+    }
+
+    #[test]
+    pub fn read_head_crest_factor() {
+        let write_head = WriteHead::from_buffer([1.0, 0.0, 0.0, 0.0]);
+        assert!((write_head.as_readhead(0).crest_factor(4) - 2.0).abs() < 1e-5);
+
+        let write_head = WriteHead::from_buffer([0.5, -0.5, 0.5, -0.5]);
+        assert!((write_head.as_readhead(0).crest_factor(4) - 1.0).abs() < 1e-5);
+
+        let write_head = WriteHead::<f32, 4>::new();
+        assert_eq!(write_head.as_readhead(0).crest_factor(4), 0.0);
+        assert_eq!(write_head.as_readhead(0).crest_factor(0), 0.0);
+    }
+
+    #[test]
+    pub fn sqrt_is_accurate() {
+        for x in [1e-6, 0.01, 0.25, 1.0, 2.0, 1234.5] {
+            assert!((sqrt(x) - x.sqrt()).abs() <= x.sqrt() * 1e-6);
+        }
+        assert_eq!(sqrt(0.0), 0.0);
+    }
+
+    #[test]
+    pub fn from_reversed_stores_input_reversed() {
+        let write_head = WriteHead::from_reversed([1, 2, 3, 4]);
+
+        let mut out = [0; 4];
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [4, 3, 2, 1]);
+    }
+
+    #[test]
+    pub fn biquad_read_head_passthrough() {
+        let write_head = WriteHead::from_buffer([0.1, -0.4, 0.25, 0.8]);
+
+        let raw = write_head.as_readhead(1);
+        let biquad = BiquadReadHead::new(write_head.as_readhead(1), [1.0, 0.0, 0.0], [0.0, 0.0]);
+        assert!(biquad.take(8).eq(raw.take(8)));
+    }
+
+    #[test]
+    pub fn interleave_two_write_heads() {
+        let left = WriteHead::from_buffer([1, 2, 3]);
+        let right = WriteHead::from_buffer([-1, -2, -3]);
+
+        let mut out = [0; 6];
+        interleave(&left, &right, &mut out);
+        assert_eq!(out, [1, -1, 2, -2, 3, -3]);
+    }
+
+    #[test]
+    pub fn write_head_copy_window_from() {
+        let src = WriteHead::from_buffer([1, 2, 3, 4]);
+        let mut dst = WriteHead::<i32, 4>::new();
+
+        dst.copy_window_from(&src, 3, 1, 2);
+        let mut out = [0; 4];
+        dst.read_storage(&mut out);
+        assert_eq!(out, [3, 0, 0, 2]);
+
+        let mut src = WriteHead::from_buffer([1, 2, 3, 4]);
+        src.set_active_len(2);
+        let mut dst = WriteHead::<i32, 4>::new();
+        dst.set_active_len(3);
+        dst.copy_window_from(&src, 2, 1, 3);
+        dst.read_storage(&mut out);
+        assert_eq!(out, [1, 2, 2, 0]);
+    }
+
+    #[test]
+    pub fn sample_hold_read_head() {
+        let write_head = WriteHead::from_buffer([1, 2, 3]);
+
+        let read_head = SampleHoldReadHead::new(write_head.as_readhead(0), 3);
+        assert!(read_head.take(10).eq([1, 1, 1, 2, 2, 2, 3, 3, 3, 1]));
+    }
+
+    #[test]
+    pub fn running_max_matches_brute_force() {
+        let mut input = WriteHead::<f32, 64>::new();
+        input.fill_noise(99);
+        let mut samples = [0.0; 64];
+        input.read_storage(&mut samples);
+
+        let mut running_max = RunningMax::<f32, 5>::new();
+        for (n, &sample) in samples.iter().enumerate() {
+            let start = n.saturating_sub(4);
+            let expected = samples[start..=n].iter().cloned().fold(f32::MIN, f32::max);
+            assert_eq!(running_max.push(sample), expected);
+        }
+    }
+
+    #[test]
+    pub fn write_head_push_foldback() {
+        let write_head = WriteHead::<f32, 4>::new();
+
+        write_head.push_foldback(0.5, 1.0);
+        assert_eq!(write_head.ago(1), 0.5);
+        write_head.push_foldback(1.25, 1.0);
+        assert_eq!(write_head.ago(1), 0.75);
+        write_head.push_foldback(-1.5, 1.0);
+        assert_eq!(write_head.ago(1), -0.5);
+        write_head.push_foldback(3.5, 1.0);
+        assert_eq!(write_head.ago(1), -0.5);
+        write_head.push_foldback(1e10, 0.5);
+        assert!(write_head.ago(1).abs() <= 0.5);
+
+        let write_head = WriteHead::<i32, 4>::new();
+        write_head.push_foldback(i32::MAX, 1);
+        assert!(write_head.ago(1).abs() <= 1);
+        write_head.push_foldback(i32::MAX, i32::MAX / 2);
+        assert!(write_head.ago(1).abs() <= i32::MAX / 2 + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "foldback threshold must be positive")]
+    pub fn push_foldback_rejects_non_positive_threshold() {
+        WriteHead::<f32, 4>::new().push_foldback(0.5, 0.0);
+    }
+
+    #[test]
+    pub fn read_head_dithered_average() {
+        let write_head = WriteHead::from_buffer([0.3_f32; 4]);
+
+        let mut read_head = write_head.as_readhead(0);
+        let mut rng_state = 1234;
+        let reads = 20000;
+        let mean = (0..reads).map(|_| read_head.read_dithered(4, &mut rng_state)).sum::<f32>() / reads as f32;
+        assert!((mean - 0.3).abs() < 0.005);
+        assert!((read_head.read_bitcrush(4) - 0.3).abs() > 0.03);
+    }
+
+    #[test]
+    pub fn write_head_oldest_newest() {
+        let write_head = WriteHead::<i32, 4>::new();
+        for n in 1..=6 {
+            write_head.push(n);
+        }
+
+        assert_eq!(write_head.oldest(), 3);
+        assert_eq!(write_head.newest(), 6);
+    }
+
+    #[test]
+    pub fn write_head_stats() {
+        let write_head = WriteHead::from_buffer([0.5, -0.25, 1.0, 0.75]);
+        assert_eq!(write_head.stats(), (-0.25, 1.0, 0.5));
+
+        let (min, max, mean) = WriteHead::from_buffer([100_i16, 300]).stats();
+        assert_eq!((min, max), (100, 300));
+        assert_eq!(mean, 200.0 / 32768.0);
+    }
+
+    #[test]
+    pub fn write_head_decay_fades_frozen_buffer() {
+        let mut write_head = WriteHead::from_buffer([1.0, -0.5, 0.25, 0.8]);
+        write_head.set_frozen(true);
+
+        for _ in 0..1000 {
+            write_head.decay(0.99);
+        }
+        let mut out = [0.0; 4];
+        write_head.read_storage(&mut out);
+        assert!(out.iter().all(|sample| sample.abs() < 1e-4));
+    }
+
+    #[test]
+    pub fn read_head_next_n_is_contiguous() {
+        let write_head = WriteHead::from_buffer([1, 2, 3, 4, 5]);
+
+        let mut read_head = write_head.as_readhead(0);
+        let mut first = [0; 2];
+        let mut second = [0; 2];
+        read_head.next_n(2, &mut first);
+        read_head.next_n(2, &mut second);
+        assert_eq!(first, [1, 2]);
+        assert_eq!(second, [3, 4]);
+        assert_eq!(read_head.next().unwrap(), 5);
+    }
+
+    #[test]
+    pub fn write_head_colocated_readhead() {
+        let write_head = WriteHead::from_buffer([1, 2, 3, 4]);
+        write_head.push(5);
+
+        let mut read_head = write_head.as_colocated_readhead();
+        assert_eq!(read_head.next().unwrap(), write_head.current());
+        assert_eq!(read_head.next().unwrap(), 3);
+    }
+
+    #[test]
+    pub fn stereo_correlation_of_identical_and_inverted() {
+        let left = WriteHead::from_buffer([0.5, -0.25, 0.75, -1.0]);
+        let right = WriteHead::from_buffer([-0.5, 0.25, -0.75, 1.0]);
+
+        let read_left = left.as_readhead(0);
+        assert!((stereo_correlation(&read_left, &left.as_readhead(0), 4) - 1.0).abs() < 1e-5);
+        assert!((stereo_correlation(&read_left, &right.as_readhead(0), 4) + 1.0).abs() < 1e-5);
+        assert_eq!(stereo_correlation(&read_left, &WriteHead::<f32, 4>::new().as_readhead(0), 4), 0.0);
+    }
+
+    #[test]
+    pub fn wavetable_read_head_is_periodic_sine() {
+        let write_head = WriteHead::<f32, 16>::new();
+        for n in 0..16 {
+            write_head.push((2.0 * core::f32::consts::PI * n as f32 / 16.0).sin());
+        }
+
+        let mut oscillator = WavetableReadHead::new(write_head.as_readhead(0));
+        oscillator.set_frequency(0.5, 16.0);
+        let out: [f32; 64] = core::array::from_fn(|_| oscillator.next().unwrap());
+        for (n, &sample) in out.iter().enumerate() {
+            let expected = (2.0 * core::f32::consts::PI * n as f32 / 32.0).sin();
+            assert!((sample - expected).abs() < 0.02);
+        }
+        for n in 0..32 {
+            assert!((out[n] - out[n + 32]).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    pub fn write_head_clip_count() {
+        let write_head = WriteHead::from_buffer([0.5, 1.0, -0.2, -1.3, 0.99]);
+        assert_eq!(write_head.clip_count(1.0), 2);
+
+        let write_head = WriteHead::from_buffer([i16::MAX, 0, -100, i16::MIN]);
+        assert_eq!(write_head.clip_count(i16::MAX), 2);
+    }
+
+    #[test]
+    pub fn write_head_apply_window() {
+        let mut write_head = WriteHead::from_buffer([0.5, -0.25, 1.0, 0.75]);
+        let mut out = [0.0; 4];
+
+        write_head.apply_window(&[1.0; 4]);
+        write_head.read_storage(&mut out);
+        assert_eq!(out, [0.5, -0.25, 1.0, 0.75]);
+
+        write_head.apply_window(&[0.0; 4]);
+        write_head.read_storage(&mut out);
+        assert!(out.iter().all(|&sample| sample == 0.0));
+    }
+
+    #[test]
+    pub fn linear_read_head_on_empty_buffer() {
+        let write_head = WriteHead::<f32, 3>::new();
+        assert_eq!(write_head.as_linear_readhead().next(), None);
+
+        let write_head = WriteHead::from_buffer([1.0, 2.0, 3.0]);
+        assert_eq!(write_head.as_linear_readhead().count(), 3);
+    }
+
+    #[test]
+    pub fn linear_read_head_next_checked_on_full_buffer() {
+        let write_head = WriteHead::<f32, 3>::new();
+        for n in 1..4 {
+            write_head.push(n as f32);
+        }
+
+        let mut read_head = write_head.as_linear_readhead();
+        for n in 1..4 {
+            assert_eq!(read_head.next_checked(&write_head), Ok(n as f32));
+        }
+        assert_eq!(read_head.next_checked(&write_head), Err(Underrun));
+        assert_eq!(write_head.as_linear_readhead().count(), 3);
+
+        write_head.push(4.0);
+        assert_eq!(write_head.as_linear_readhead().count(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "loop length must be non-zero")]
+    pub fn loop_readhead_rejects_empty_region() {
+        WriteHead::<f32, 4>::new().as_loop_readhead(1, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "loop region must lie within the active region")]
+    pub fn loop_readhead_rejects_region_past_end() {
+        WriteHead::<f32, 4>::new().as_loop_readhead(3, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "loop region must lie within the active region")]
+    pub fn loop_readhead_rejects_region_past_active_len() {
+        let mut write_head = WriteHead::<f32, 4>::new();
+        write_head.set_active_len(2);
+        write_head.as_loop_readhead(1, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "loop region must lie within the active region")]
+    pub fn loop_readhead_rejects_overflowing_region() {
+        WriteHead::<f32, 4>::new().as_loop_readhead(usize::MAX, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "read head must tap the whole active region of this write head")]
+    pub fn samples_available_rejects_loop_head() {
+        let write_head = WriteHead::<f32, 4>::new();
+        reported_latency(&write_head.as_loop_readhead(1, 2), &write_head);
+    }
+
+    #[test]
+    #[should_panic(expected = "read head must tap the whole active region of this write head")]
+    pub fn samples_available_rejects_head_over_other_buffer() {
+        let write_head = WriteHead::<f32, 4>::new();
+        let other = WriteHead::<f32, 4>::new();
+        samples_available(&other.as_readhead(1), &write_head);
+    }
+
+    #[test]
+    pub fn active_len_wraps_indexing_and_taps() {
+        let mut write_head = WriteHead::<i32, 5>::new();
+        write_head.set_active_len(2);
+        write_head.seek(1);
+
+        write_head.add_slice(&[1, 1]);
+        let mut storage = [0; 5];
+        write_head.read_storage(&mut storage);
+        assert_eq!(storage, [1, 1, 0, 0, 0]);
+
+        write_head[2].set(7);
+        assert_eq!(write_head.as_readhead(0).next(), Some(7));
+
+        assert!(write_head.as_readhead(3).eq_window(&[1, 7]));
+        assert!(write_head.as_readhead_const::<4>().eq_window(&[7, 1]));
+    }
+
+    #[test]
+    pub fn aggregates_cover_only_active_region() {
+        let mut write_head = WriteHead::from_buffer([2.0, 4.0, 9.0]);
+        write_head.set_active_len(2);
+
+        assert_eq!(write_head.mean(), 3.0);
+        assert_eq!(write_head.sum_samples(), 6.0);
+        assert_eq!(write_head.nonzero_count(), 2);
+        assert_eq!(write_head.clip_count(5.0), 0);
+
+        write_head.normalize();
+        let mut storage = [0.0; 3];
+        write_head.read_storage(&mut storage);
+        assert_eq!(storage, [0.5, 1.0, 9.0]);
+    }
+
+    #[test]
+    pub fn wavetable_read_head_wraps_large_increments() {
+        let write_head = WriteHead::from_buffer([0.0, 1.0, 0.0, -1.0]);
+
+        let mut oscillator = WavetableReadHead::new(write_head.as_readhead(0));
+        oscillator.set_frequency(1e12, 1.0);
+        assert!(oscillator.take(4).all(|sample: f32| sample.abs() <= 1.0));
+
+        let mut oscillator = WavetableReadHead::new(write_head.as_readhead(0));
+        oscillator.set_frequency(-1.0, 4.0);
+        assert!(oscillator.take(4).eq([0.0, -1.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    #[should_panic(expected = "frequency and sample rate must give a finite phase increment")]
+    pub fn wavetable_read_head_rejects_zero_sample_rate() {
+        let write_head = WriteHead::<f32, 4>::new();
+        WavetableReadHead::new(write_head.as_readhead(0)).set_frequency(440.0, 0.0);
     }
 }